@@ -4,6 +4,12 @@ use winit::event::VirtualKeyCode;
 
 use crate::manifold::{Point, Manifold};
 
+/// How far one key press moves the camera
+const MOVEMENT_STEP: f32 = 0.2;
+
+/// How many times a step through a wall is halved looking for the wall
+const WALL_BISECTIONS: usize = 16;
+
 #[derive(Debug, Copy, Clone)]
 pub struct Info {
     x: Vector4<f32>,
@@ -46,23 +52,77 @@ impl Info {
     pub fn movement(&mut self, keycode: VirtualKeyCode, manifold: &impl Manifold) {
         use VirtualKeyCode as VKC;
 
-        let self_info: (&mut Vector4<f32>, &mut Vector4<f32>, f32) = match keycode {
-            VKC::D      => (&mut self.p, &mut self.x,  1.0),
-            VKC::A      => (&mut self.p, &mut self.x, -1.0),
-            VKC::Space  => (&mut self.p, &mut self.y,  1.0),
-            VKC::LShift => (&mut self.p, &mut self.y, -1.0),
-            VKC::W      => (&mut self.p, &mut self.z,  1.0),
-            VKC::S      => (&mut self.p, &mut self.z, -1.0),
+        let (axis, direction) = match keycode {
+            VKC::D      => (0,  1.0),
+            VKC::A      => (0, -1.0),
+            VKC::Space  => (1,  1.0),
+            VKC::LShift => (1, -1.0),
+            VKC::W      => (2,  1.0),
+            VKC::S      => (2, -1.0),
             _ => return
         };
 
-        let advanced_point = manifold.advance_point(Point {
-            pos: *self_info.0,
-            ray: self_info.2 * (*self_info.1)
-        }, 0.2);
+        // The whole frame is carried along so glide reflections mirror the camera too
+        let advance = |distance: f32| {
+            let mut frame = [self.x, self.y, self.z];
+            let advanced_point = manifold.advance_frame(Point {
+                pos: self.p,
+                ray: direction * frame[axis]
+            }, &mut frame, distance);
+
+            (advanced_point, frame)
+        };
+
+        let (mut advanced_point, mut frame) = advance(MOVEMENT_STEP);
+
+        // Steps through a wall are shortened to the last distance found in bounds, so the camera stops at the wall
+        if !manifold.in_bounds(advanced_point.pos) {
+            let (mut inside, mut outside) = (0.0, MOVEMENT_STEP);
+
+            for _ in 0..WALL_BISECTIONS {
+                let middle = 0.5 * (inside + outside);
+
+                if manifold.in_bounds(advance(middle).0.pos) {
+                    inside = middle;
+                } else {
+                    outside = middle;
+                }
+            }
+
+            if inside == 0.0 {
+                return;
+            }
+
+            (advanced_point, frame) = advance(inside);
+        }
+
+        // The ray is the geodesic's own velocity, which is parallel, and the other axes are transported
+        // then kept perpendicular to it
+        let pos = advanced_point.pos;
+        let dot = |a: Vector4<f32>, b: Vector4<f32>| manifold.inner_product(pos, a, b);
+        let normalize = |v: Vector4<f32>| v / dot(v, v).max(1e-12).sqrt();
+        let ray = direction * normalize(advanced_point.ray);
+
+        for (i, vector) in frame.iter_mut().enumerate() {
+            *vector = if i == axis {
+                ray
+            } else {
+                let transported = manifold.parallel_transport(*vector, pos);
+
+                normalize(transported - ray * dot(ray, transported))
+            };
+        }
+
+        let (first, second) = match axis {
+            0 => (1, 2),
+            1 => (0, 2),
+            _ => (0, 1)
+        };
+
+        frame[second] = normalize(frame[second] - frame[first] * dot(frame[first], frame[second]));
 
-        *self_info.0 = advanced_point.pos;
-        *self_info.1 = self_info.2 * advanced_point.ray;
+        self.p = pos;
+        [self.x, self.y, self.z] = frame;
     }
 
     pub fn rotate_around_y(&mut self, xz_angle: f32, yz_angle: f32) {
@@ -74,16 +134,20 @@ impl Info {
         rotate_between(&mut self.y, &mut self.z, -yz_angle);
     }
 
-    pub fn reorient(&mut self) {
-        self.x = self.x.normalize();
-        self.y = self.y.normalize();
-        self.z = self.z.normalize();
+    /// Makes the camera's axes an orthonormal basis of the manifold's tangent space, in its inner_product
+    pub fn reorient(&mut self, manifold: &impl Manifold) {
+        let normal = manifold.normal_at(self.p);
+        let dot = |a: Vector4<f32>, b: Vector4<f32>| manifold.inner_product(self.p, a, b);
+        let normalize = |v: Vector4<f32>| v / dot(v, v).max(1e-12).sqrt();
 
-        self.y = self.y - self.x * self.x.dot(&self.y);
-        self.y = self.y.normalize();
+        let x = normalize(self.x - normal * normal.dot(&self.x));
+        let y = normalize(self.y - normal * normal.dot(&self.y));
+        let z = normalize(self.z - normal * normal.dot(&self.z));
 
-        self.z = self.z - self.x * self.x.dot(&self.z) - self.y * self.y.dot(&self.z);
-        self.z = self.z.normalize();
+        let y = normalize(y - x * dot(x, y));
+        let z = normalize(z - x * dot(x, z) - y * dot(y, z));
+
+        (self.x, self.y, self.z) = (x, y, z);
     }
 }
 
@@ -122,4 +186,90 @@ impl Geometry {
             Self::Function(id) => [0, *id, 0, 0]
         }).to_owned()
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::PI;
+    use crate::manifold::shapes4D::{Hyperplane, Hypersphere};
+    use crate::manifold::boundary::{Bounded, Wall};
+    use crate::manifold::intrinsic::Hyperbolic;
+    use super::*;
+
+    fn walk(info: &mut Info, manifold: &impl Manifold, keycode: VirtualKeyCode, steps: usize) {
+        for _ in 0..steps {
+            info.movement(keycode, manifold);
+        }
+    }
+
+    #[test]
+    fn test_parallel_transport_holonomy() {
+        // Three quarter great circles with right turns between them close up into a triangle covering an
+        // eighth of the sphere, which turns the camera by a right angle
+        let sphere = Hypersphere::new(0.4 * 20.0 / PI);
+        let mut info = Info::default();
+
+        walk(&mut info, &sphere, VirtualKeyCode::W, 20);
+        walk(&mut info, &sphere, VirtualKeyCode::D, 20);
+        walk(&mut info, &sphere, VirtualKeyCode::S, 20);
+
+        assert!(info.p.magnitude() < 1e-3);
+        assert!((info.x + Vector4::z()).magnitude() < 1e-3);
+        assert!((info.y - Vector4::y()).magnitude() < 1e-3);
+        assert!((info.z - Vector4::x()).magnitude() < 1e-3);
+
+        // Flat space has no holonomy
+        let mut info = Info::default();
+
+        walk(&mut info, &Hyperplane, VirtualKeyCode::W, 20);
+        walk(&mut info, &Hyperplane, VirtualKeyCode::D, 20);
+        walk(&mut info, &Hyperplane, VirtualKeyCode::S, 20);
+        walk(&mut info, &Hyperplane, VirtualKeyCode::A, 20);
+
+        assert!(info.p.magnitude() < 1e-3);
+        assert!((info.x - Vector4::x()).magnitude() < 1e-3);
+        assert!((info.z - Vector4::z()).magnitude() < 1e-3);
+    }
+
+    #[test]
+    fn test_hyperbolic_frames_stay_orthonormal() {
+        let hyperbolic = Hyperbolic::new(4.0);
+        let mut info = Info::default();
+
+        for keycode in [VirtualKeyCode::W, VirtualKeyCode::D, VirtualKeyCode::S, VirtualKeyCode::A] {
+            walk(&mut info, &hyperbolic, keycode, 20);
+
+            let frame = [info.x, info.y, info.z];
+
+            // Orthonormal in the Minkowski metric and still tangent to the hyperboloid
+            for i in 0..3 {
+                for j in 0..3 {
+                    let expected = if i == j { 1.0 } else { 0.0 };
+
+                    assert!((hyperbolic.inner_product(info.p, frame[i], frame[j]) - expected).abs() < 1e-3);
+                }
+
+                assert!((hyperbolic.parallel_transport(frame[i], info.p) - frame[i]).magnitude() < 1e-3);
+            }
+        }
+
+        assert!(hyperbolic.is_on_curve(info.p));
+    }
+
+    #[test]
+    fn test_walls_stop_movement() {
+        let room = Bounded::new(Hyperplane, vec![Wall::below(2, 1.0)]);
+        let mut info = Info::default();
+
+        walk(&mut info, &room, VirtualKeyCode::W, 20);
+
+        // It stops at the wall instead of up to a whole step short of it
+        assert!(room.in_bounds(info.p));
+        assert!(info.p.z > 0.999);
+
+        // Walking away from the wall still works
+        walk(&mut info, &room, VirtualKeyCode::S, 5);
+
+        assert!(info.p.z < 0.1);
+    }
+}
@@ -34,7 +34,6 @@ const IDEAL_FPS: f32 = 20.0;
 use wasm_bindgen::prelude::*;
 
 use crate::manifold::*;
-use crate::manifold::shapes3D::{ExtrudedShape, Sphere, Torus};
 
 #[cfg_attr(target_arch="wasm32", wasm_bindgen(start))]
 pub async fn run() {
@@ -84,9 +83,33 @@ pub async fn run() {
         // Hyperplane,
         // Hypersphere::new(10.0),
         // Hypersphube::new(10.0, 2.0),
+        // Hyperellipsoid::new(nalgebra::Vector4::new(10.0, 6.0, 8.0, 5.0)),
+        // Superellipsoid::new(nalgebra::Vector4::new(10.0, 6.0, 8.0, 5.0), 4.0, SuperellipsoidProjection::Closest),
         Ditorus::new(10.0, 8.0, 3.0),
-        // ExtrudedShape(Sphere::new(10.0)),
-        // ExtrudedShape(Torus::new(7.0, 5.0)),
+        // Tiger::new(10.0, 8.0, 3.0, TigerPairing::XyZw, true),
+        // parser::parse_implicit(
+        //     "(sqrt(x^2 + w^2) - R)^2 + y^2 + z^2 = r^2",
+        //     &[("R", 10.0), ("r", 3.0)],
+        //     nalgebra::Vector4::w() * 13.0
+        // ).unwrap(),
+        // shapes3D::ExtrudedShape::new(shapes3D::Sphere::new(10.0), shapes3D::ExtrusionAxis::Z, None),
+        // shapes3D::ExtrudedShape::new(shapes3D::Torus::new(7.0, 5.0), shapes3D::ExtrusionAxis::Z, None),
+        // shapes3D::ExtrudedShape::new(shapes3D::Sphere::new(10.0), shapes3D::ExtrusionAxis::Direction(nalgebra::Vector4::new(1.0, 0.0, 1.0, 0.0)), Some(30.0)),
+        // shapes3D::SpunShape::new(shapes3D::Torus::new(7.0, 3.0), 12.0, shapes3D::SpinPlane::Yz),
+        // heightfield::Heightfield::from_height(heightfield::Noise::new(1, 5, 8.0, 0.05)),
+        // tube::TubeAroundCurve::torus_knot(2, 3, 20.0, 8.0, 58, 3.0),
+        // shapes3D::ExtrudedShape::new(shapes3D::Capsule::new(5.0, 10.0), shapes3D::ExtrusionAxis::Z, Some(30.0)),
+        // shapes3D::SpunShape::new(shapes3D::Superquadric::new(nalgebra::Vector3::new(4.0, 6.0, 3.0), 4.0), 12.0, shapes3D::SpinPlane::Xz),
+        // product::Product::new(shapes2D::Circle::new(10.0), shapes2D::Plane, TigerPairing::XwZy),
+        // product::Duocylinder::new(8.0, 10.0, TigerPairing::XyZw),
+        // transformed::Transformed::new(Hypersphube::new(10.0, 2.0), transformed::plane_rotation(0, 1, 0.8), nalgebra::Vector4::zeros(), 1.0),
+        // implicit::ImplicitManifold::from_field(csg::SmoothUnion::new(Hypersphere::new(10.0), Ditorus::new(14.0, 8.0, 3.0), 3.0), nalgebra::Vector4::zeros()),
+        // quotient::Quotient::three_torus(Hyperplane, nalgebra::Vector3::new(12.0, 12.0, 12.0)),
+        // quotient::Quotient::klein_bottle_circle(Hyperplane, nalgebra::Vector3::new(12.0, 12.0, 12.0)),
+        // intrinsic::Hyperbolic::new(10.0),
+        // intrinsic::ThreeSphere::new(10.0),
+        // integrator::Rk4::new(Ditorus::new(10.0, 8.0, 3.0), 1e-3),
+        // boundary::Bounded::room(Hyperplane, nalgebra::Vector3::new(-6.0, -3.0, -6.0), nalgebra::Vector3::new(6.0, 3.0, 6.0)),
         info,
         Vec::new()
     ).await;
@@ -101,7 +124,7 @@ pub async fn run() {
             // game_loop.game.info.print_position();
 
             if game_loop.number_of_updates() % 1 == 0 {
-                game_loop.game.info.reorient();
+                game_loop.game.info.reorient(&game_loop.game.manifold);
             }
 
             for active_keycode in game_loop.game.active_keycodes.iter() {
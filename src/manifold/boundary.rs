@@ -0,0 +1,202 @@
+use nalgebra::{Vector3, Vector4};
+use winit::event::VirtualKeyCode;
+use crate::manifold::{Manifold, Point, vec4_wgsl};
+
+/// A cut through the ambient space, the side where contains is true is kept
+#[derive(Debug, Clone, Copy)]
+pub enum Wall {
+    /// Keeps dot(normal, pos) <= offset
+    HalfSpace { normal: Vector4<f32>, offset: f32 },
+    /// Keeps the points closer to center than radius
+    Ball { center: Vector4<f32>, radius: f32 }
+}
+
+impl Wall {
+    pub fn half_space(normal: Vector4<f32>, offset: f32) -> Self {
+        let length = normal.magnitude();
+
+        assert!(length > 0.0, "normal can't be zero");
+
+        Self::HalfSpace { normal: normal / length, offset: offset / length }
+    }
+
+    /// Keeps pos[axis] <= value, like w < 5
+    pub fn below(axis: usize, value: f32) -> Self {
+        Self::half_space(Vector4::ith(axis, 1.0), value)
+    }
+
+    /// Keeps pos[axis] >= value
+    pub fn above(axis: usize, value: f32) -> Self {
+        Self::half_space(Vector4::ith(axis, -1.0), -value)
+    }
+
+    pub fn ball(center: Vector4<f32>, radius: f32) -> Self {
+        assert!(radius > 0.0, "radius must be positive");
+
+        Self::Ball { center, radius }
+    }
+
+    pub fn contains(&self, pos: Vector4<f32>) -> bool {
+        match self {
+            Self::HalfSpace { normal, offset } => normal.dot(&pos) <= *offset,
+            Self::Ball { center, radius } => (pos - center).magnitude() < *radius
+        }
+    }
+
+    /// contains as a wgsl expression of pos
+    fn contains_wgsl(&self) -> String {
+        match self {
+            Self::HalfSpace { normal, offset } => format!("dot({}, pos) <= {:?}", vec4_wgsl(*normal), offset),
+            Self::Ball { center, radius } => format!("length(pos - {}) < {:?}", vec4_wgsl(*center), radius)
+        }
+    }
+}
+
+/// A manifold with walls, rays stop on them and are drawn in the wall color and the camera can't walk through them.
+/// Bounded can't be nested, put all of the walls in one.
+#[derive(Debug)]
+pub struct Bounded<M: Manifold> {
+    manifold: M,
+    walls: Vec<Wall>
+}
+
+impl<M: Manifold> Bounded<M> {
+    pub fn new(manifold: M, walls: Vec<Wall>) -> Self {
+        Self { manifold, walls }
+    }
+
+    /// The box between min and max
+    pub fn room(manifold: M, min: Vector3<f32>, max: Vector3<f32>) -> Self {
+        Self::new(manifold, (0..3).flat_map(|i| [Wall::above(i, min[i]), Wall::below(i, max[i])]).collect())
+    }
+
+    pub fn manifold(&self) -> &M {
+        &self.manifold
+    }
+}
+
+impl<M: Manifold> Manifold for Bounded<M> {
+    fn description(&self) -> String {
+        format!("{} with {} walls", self.manifold.description(), self.walls.len())
+    }
+
+    fn project_onto_wgsl(&self) -> String {
+        self.manifold.project_onto_wgsl()
+    }
+
+    fn project_onto(&self, pos: Vector4<f32>) -> Vector4<f32> {
+        self.manifold.project_onto(pos)
+    }
+
+    fn step_along_curve(&self, point: Point, delta: f32) -> Point {
+        self.manifold.step_along_curve(point, delta)
+    }
+
+    fn step_along_curve_wgsl(&self) -> String {
+        self.manifold.step_along_curve_wgsl()
+    }
+
+    fn geodesic(&self, point: &Point, t: f32) -> Option<Point> {
+        self.manifold.geodesic(point, t)
+    }
+
+    fn geodesic_wgsl(&self) -> Option<String> {
+        self.manifold.geodesic_wgsl()
+    }
+
+    fn normal_at(&self, pos: Vector4<f32>) -> Vector4<f32> {
+        self.manifold.normal_at(pos)
+    }
+
+    fn normal_at_wgsl(&self) -> String {
+        self.manifold.normal_at_wgsl()
+    }
+
+    fn inner_product(&self, pos: Vector4<f32>, a: Vector4<f32>, b: Vector4<f32>) -> f32 {
+        self.manifold.inner_product(pos, a, b)
+    }
+
+    fn tangent_basis(&self, pos: Vector4<f32>) -> [Vector4<f32>; 3] {
+        self.manifold.tangent_basis(pos)
+    }
+
+    fn parallel_transport(&self, vector: Vector4<f32>, pos: Vector4<f32>) -> Vector4<f32> {
+        self.manifold.parallel_transport(vector, pos)
+    }
+
+    fn identify(&self, point: Point, frame: &mut [Vector4<f32>]) -> Point {
+        self.manifold.identify(point, frame)
+    }
+
+    fn identify_wgsl(&self) -> String {
+        self.manifold.identify_wgsl()
+    }
+
+    fn intrinsic_coords(&self, pos: Vector4<f32>) -> Vector3<f32> {
+        self.manifold.intrinsic_coords(pos)
+    }
+
+    fn intrinsic_coords_wgsl(&self) -> String {
+        self.manifold.intrinsic_coords_wgsl()
+    }
+
+    fn point_at_intrinsic_coords(&self, coords: Vector3<f32>) -> Vector4<f32> {
+        self.manifold.point_at_intrinsic_coords(coords)
+    }
+
+    fn point_at_intrinsic_coords_wgsl(&self) -> String {
+        self.manifold.point_at_intrinsic_coords_wgsl()
+    }
+
+    fn in_bounds(&self, pos: Vector4<f32>) -> bool {
+        self.walls.iter().all(|wall| wall.contains(pos)) && self.manifold.in_bounds(pos)
+    }
+
+    fn in_bounds_wgsl(&self) -> String {
+        let walls: Vec<String> = self.walls.iter().map(|wall| format!("({})", wall.contains_wgsl())).collect();
+
+        format!("\
+                return {} && in_bounds_inner(pos);\
+          \n}}\
+          \n\
+          \nfn in_bounds_inner(pos: vec4<f32>) -> bool {{\
+          \n    {}\
+            ",
+            if walls.is_empty() { String::from("true") } else { walls.join(" && ") },
+            self.manifold.in_bounds_wgsl()
+        )
+    }
+
+    fn is_on_curve(&self, pos: Vector4<f32>) -> bool {
+        self.manifold.is_on_curve(pos)
+    }
+
+    fn get_bytes(&self) -> Vec<u8> {
+        self.manifold.get_bytes()
+    }
+
+    fn change_on_keybinds(&mut self, key_code: &VirtualKeyCode) {
+        self.manifold.change_on_keybinds(key_code);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::manifold::shapes4D::{Hyperplane, Hypersphere};
+    use super::*;
+
+    #[test]
+    fn test_walls() {
+        let room = Bounded::room(Hyperplane, Vector3::new(-2.0, -1.0, -3.0), Vector3::new(2.0, 1.0, 3.0));
+
+        assert!(room.in_bounds(Vector4::new(1.9, 0.0, -2.9, 0.0)));
+        assert!(!room.in_bounds(Vector4::new(2.1, 0.0, 0.0, 0.0)));
+        assert!(!room.in_bounds(Vector4::new(0.0, -1.1, 0.0, 0.0)));
+
+        let cap = Bounded::new(Hypersphere::new(3.0), vec![Wall::below(3, 5.0), Wall::ball(Vector4::zeros(), 5.5)]);
+
+        assert!(cap.in_bounds(Vector4::new(3.0, 0.0, 0.0, 3.0)));
+        assert!(!cap.in_bounds(Vector4::new(0.0, 0.0, 0.0, 6.0)));
+        assert!(!cap.in_bounds(Vector4::new(0.0, 2.8, 0.0, 4.8)));
+    }
+}
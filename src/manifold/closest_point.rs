@@ -0,0 +1,262 @@
+use nalgebra::SVector;
+
+const ELLIPSOID_ITERATIONS: u32 = 32;
+const SUPERELLIPSOID_ITERATIONS: u32 = 8;
+const NEWTON_ITERATIONS: u32 = 8;
+
+/// Combines the components of a wgsl vector with a two argument function, like max(v.x, max(v.y, v.z))
+fn fold_components_wgsl(dimension: usize, vector: &str, function: &str) -> String {
+    let components = &["x", "y", "z", "w"][..dimension];
+
+    components[..dimension - 1].iter().rev()
+        .fold(format!("{}.{}", vector, components[dimension - 1]), |inner, component| {
+            format!("{}({}.{}, {})", function, vector, component, inner)
+        })
+}
+
+/// The closest point to pos on the ellipsoid sum (x_i / r_i)^2 = 1 centered on the origin.
+/// The closest point is r_i^2 p_i / (t + r_i^2) for the root t of sum (r_i p_i / (t + r_i^2))^2 = 1,
+/// which is found by bisection after reflecting pos into the positive orthant.
+pub(crate) fn closest_on_ellipsoid<const D: usize>(pos: SVector<f32, D>, radii: SVector<f32, D>) -> SVector<f32, D> {
+    let p = pos.map(|x| x.abs().max(0.0001));
+    let radii_squared = radii.component_mul(&radii);
+    let scaled = radii.component_mul(&p);
+
+    let mut low = (scaled - radii_squared).max();
+    let mut high = scaled.magnitude() - radii.min().powi(2);
+
+    for _ in 0..ELLIPSOID_ITERATIONS {
+        let t = 0.5 * (low + high);
+
+        if scaled.component_div(&radii_squared.add_scalar(t)).magnitude_squared() > 1.0 {
+            low = t;
+        } else {
+            high = t;
+        }
+    }
+
+    radii_squared.component_mul(&p)
+        .component_div(&radii_squared.add_scalar(0.5 * (low + high)))
+        .zip_map(&pos, |x, sign| x.copysign(sign))
+}
+
+/// A wgsl function body doing closest_on_ellipsoid for `pos: vec{dimension}<f32>`
+pub(crate) fn closest_on_ellipsoid_wgsl(dimension: usize, center: &str, radii: &str) -> String {
+    format!("\
+            let radii = {radii};\
+      \n    let pos2 = pos - {center};\
+      \n    let p = max(abs(pos2), vec{n}<f32>(0.0001));\
+      \n    let radii_squared = radii * radii;\
+      \n    let scaled = radii * p;\
+      \n    let low_bounds = scaled - radii_squared;\
+      \n    let min_radius = {min_radius};\
+      \n    var low = {low};\
+      \n    var high = length(scaled) - min_radius * min_radius;\
+      \n    \
+      \n    for (var i = 0; i < {iterations}; i += 1) {{\
+      \n        let t = 0.5 * (low + high);\
+      \n        let q = scaled / (t + radii_squared);\
+      \n        \
+      \n        if (dot(q, q) > 1.0) {{\
+      \n            low = t;\
+      \n        }} else {{\
+      \n            high = t;\
+      \n        }}\
+      \n    }}\
+      \n    \
+      \n    let signs = select(vec{n}<f32>(-1.0), vec{n}<f32>(1.0), pos2 >= vec{n}<f32>(0.0));\
+      \n    \
+      \n    return signs * radii_squared * p / (0.5 * (low + high) + radii_squared) + {center};\
+        ",
+        radii = radii,
+        center = center,
+        n = dimension,
+        min_radius = fold_components_wgsl(dimension, "radii", "min"),
+        low = fold_components_wgsl(dimension, "low_bounds", "max"),
+        iterations = ELLIPSOID_ITERATIONS
+    )
+}
+
+/// Scales pos onto the superellipsoid sum |x_i / r_i|^exponent = 1, which is not the closest point
+pub(crate) fn radial_on_superellipsoid<const D: usize>(pos: SVector<f32, D>, radii: SVector<f32, D>, exponent: f32) -> SVector<f32, D> {
+    let sum: f32 = pos.component_div(&radii).iter()
+        .map(|x| x.abs().powf(exponent))
+        .sum();
+
+    pos * sum.powf(-1.0 / exponent)
+}
+
+/// The closest point to pos on the superellipsoid sum |x_i / r_i|^exponent = 1 centered on the origin.
+/// After reflecting pos into the positive orthant and starting from the nearest of the radial projection and moving along each axis, it is repeatedly projected onto the tangent space at the current point
+/// and scaled back onto the surface, then Newton's method finishes solving x - pos + lambda * grad F(x) = 0, F(x) = 1
+/// where the hessian of F is diagonal so each step has a closed form.
+pub(crate) fn closest_on_superellipsoid<const D: usize>(pos: SVector<f32, D>, radii: SVector<f32, D>, exponent: f32) -> SVector<f32, D> {
+    let p = pos.map(|x| x.abs().max(0.0001));
+    let scaled = |x: SVector<f32, D>| x.component_div(&radii).map(|x| x.abs().max(1e-6));
+    let gradient = |x: SVector<f32, D>| scaled(x)
+        .map(|s| exponent * s.powf(exponent - 1.0))
+        .zip_map(&x, |g, x| g.copysign(x))
+        .component_div(&radii);
+    let hessian = |x: SVector<f32, D>| scaled(x)
+        .map(|s| exponent * (exponent - 1.0) * s.powf(exponent - 2.0))
+        .component_div(&radii.component_mul(&radii));
+
+    // Points inside might be closer to where they'd hit the surface going straight along an axis
+    let powers = scaled(p).map(|s| s.powf(exponent));
+    let mut new_pos = radial_on_superellipsoid(p, radii, exponent);
+
+    for i in 0..D {
+        let rest = powers.sum() - powers[i];
+
+        if rest < 1.0 {
+            let mut candidate = p;
+            candidate[i] = radii[i] * (1.0 - rest).powf(1.0 / exponent);
+
+            if (candidate - p).magnitude() < (new_pos - p).magnitude() {
+                new_pos = candidate;
+            }
+        }
+    }
+
+    for _ in 0..SUPERELLIPSOID_ITERATIONS {
+        let normal = gradient(new_pos).normalize();
+        let tangent_pos = p - (p - new_pos).dot(&normal) * normal;
+
+        new_pos = radial_on_superellipsoid(0.5 * (new_pos + tangent_pos), radii, exponent);
+    }
+
+    let mut lambda = (p - new_pos).dot(&gradient(new_pos)) / gradient(new_pos).magnitude_squared();
+
+    for _ in 0..NEWTON_ITERATIONS {
+        let (g, h) = (gradient(new_pos), hessian(new_pos));
+        let residual = new_pos - p + lambda * g;
+        let residual_f = scaled(new_pos).map(|s| s.powf(exponent)).sum() - 1.0;
+        let diagonal = h.map(|h| 1.0 + lambda * h);
+
+        let delta_lambda = (residual_f - g.component_mul(&residual).component_div(&diagonal).sum()) /
+            g.component_mul(&g).component_div(&diagonal).sum();
+
+        new_pos -= (residual + delta_lambda * g).component_div(&diagonal);
+        lambda += delta_lambda;
+    }
+
+    radial_on_superellipsoid(new_pos, radii, exponent).zip_map(&pos, |x, sign| x.copysign(sign))
+}
+
+/// A wgsl function body doing closest_on_superellipsoid for `pos: vec{dimension}<f32>`
+pub(crate) fn closest_on_superellipsoid_wgsl(dimension: usize, center: &str, radii: &str, exponent: &str) -> String {
+    format!("\
+            let radii = {radii};\
+      \n    let exponent = {exponent};\
+      \n    let pos2 = pos - {center};\
+      \n    let p = max(abs(pos2), vec{n}<f32>(0.0001));\
+      \n    let ones = vec{n}<f32>(1.0);\
+      \n    let tiny = vec{n}<f32>(1e-6);\
+      \n    let powers = pow(max(p / radii, tiny), exponent * ones);\
+      \n    var new_pos = p * pow(dot(powers, ones), -1.0 / exponent);\
+      \n    \
+      \n    for (var i = 0; i < {n}; i += 1) {{\
+      \n        let rest = dot(powers, ones) - powers[i];\
+      \n        var candidate = p;\
+      \n        candidate[i] = radii[i] * pow(max(1.0 - rest, 0.0), 1.0 / exponent);\
+      \n        \
+      \n        if (rest < 1.0 && distance(candidate, p) < distance(new_pos, p)) {{\
+      \n            new_pos = candidate;\
+      \n        }}\
+      \n    }}\
+      \n    \
+      \n    for (var i = 0; i < {iterations}; i += 1) {{\
+      \n        let normal = normalize(sign(new_pos) * pow(max(abs(new_pos / radii), tiny), (exponent - 1.0) * ones) / radii);\
+      \n        let tangent_pos = 0.5 * (new_pos + p - dot(p - new_pos, normal) * normal);\
+      \n        \
+      \n        new_pos = tangent_pos * pow(dot(pow(max(abs(tangent_pos / radii), tiny), exponent * ones), ones), -1.0 / exponent);\
+      \n    }}\
+      \n    \
+      \n    let start_gradient = sign(new_pos) * exponent * pow(max(abs(new_pos / radii), tiny), (exponent - 1.0) * ones) / radii;\
+      \n    var lambda = dot(p - new_pos, start_gradient) / dot(start_gradient, start_gradient);\
+      \n    \
+      \n    for (var i = 0; i < {newton_iterations}; i += 1) {{\
+      \n        let scaled = max(abs(new_pos / radii), tiny);\
+      \n        let gradient = sign(new_pos) * exponent * pow(scaled, (exponent - 1.0) * ones) / radii;\
+      \n        let hessian = exponent * (exponent - 1.0) * pow(scaled, (exponent - 2.0) * ones) / (radii * radii);\
+      \n        let residual = new_pos - p + lambda * gradient;\
+      \n        let residual_f = dot(pow(scaled, exponent * ones), ones) - 1.0;\
+      \n        let diagonal = 1.0 + lambda * hessian;\
+      \n        let delta_lambda = (residual_f - dot(gradient, residual / diagonal)) / dot(gradient, gradient / diagonal);\
+      \n        \
+      \n        new_pos -= (residual + delta_lambda * gradient) / diagonal;\
+      \n        lambda += delta_lambda;\
+      \n    }}\
+      \n    \
+      \n    let signs = select(-ones, ones, pos2 >= vec{n}<f32>(0.0));\
+      \n    \
+      \n    return signs * new_pos * pow(dot(pow(max(abs(new_pos / radii), tiny), exponent * ones), ones), -1.0 / exponent) + {center};\
+        ",
+        radii = radii,
+        exponent = exponent,
+        center = center,
+        n = dimension,
+        iterations = SUPERELLIPSOID_ITERATIONS,
+        newton_iterations = NEWTON_ITERATIONS
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::{Vector3, Vector4};
+    use super::*;
+
+    /// Checks the projection against the closest of many points spread over the surface
+    fn assert_closest(projection: impl Fn(Vector3<f32>) -> Vector3<f32>, radial: impl Fn(Vector3<f32>) -> Vector3<f32>) {
+        let samples = (0..200).flat_map(|i| (0..400).map(move |j| {
+            let (theta, phi) = (std::f32::consts::PI * (i as f32 + 0.5) / 200.0, std::f32::consts::TAU * j as f32 / 400.0);
+
+            Vector3::new(theta.sin() * phi.cos(), theta.sin() * phi.sin(), theta.cos())
+        })).map(&radial).collect::<Vec<_>>();
+
+        for pos in [Vector3::new(3.0, 1.0, 0.5), Vector3::new(0.2, -0.3, 0.1), Vector3::new(-1.0, 4.0, -2.0), Vector3::new(0.5, 0.0, 0.0)] {
+            let distance = (projection(pos) - pos).magnitude();
+            let sampled_distance = samples.iter()
+                .map(|sample| (sample - pos).magnitude())
+                .fold(f32::INFINITY, f32::min);
+
+            assert!((radial(projection(pos)) - projection(pos)).magnitude() < 1e-3, "{:?} isn't on the surface", pos);
+            assert!(distance < sampled_distance + 1e-3, "{:?}: {} vs {}", pos, distance, sampled_distance);
+            assert!(distance > sampled_distance - 0.05, "{:?}: {} vs {}", pos, distance, sampled_distance);
+        }
+    }
+
+    #[test]
+    fn test_closest_on_ellipsoid() {
+        let radii = Vector3::new(3.0, 1.0, 2.0);
+
+        assert_closest(|pos| closest_on_ellipsoid(pos, radii), |pos| radial_on_superellipsoid(pos, radii, 2.0));
+    }
+
+    #[test]
+    fn test_closest_on_superellipsoid() {
+        let radii = Vector3::new(2.0, 1.0, 1.5);
+
+        for exponent in [1.5, 2.0, 4.0] {
+            assert_closest(
+                |pos| closest_on_superellipsoid(pos, radii, exponent),
+                |pos| radial_on_superellipsoid(pos, radii, exponent)
+            );
+        }
+    }
+
+    #[test]
+    fn test_closest_on_superellipsoid_4d() {
+        let radii = Vector4::new(3.0, 2.0, 4.0, 2.5);
+
+        for pos in [Vector4::new(4.0, 3.0, -1.0, 2.0), Vector4::new(0.5, 0.2, -0.1, 0.3), Vector4::new(-1.0, 0.0, 6.0, 0.0)] {
+            let closest = closest_on_superellipsoid(pos, radii, 4.0);
+            let radial = radial_on_superellipsoid(pos, radii, 4.0);
+            let normal = closest.component_div(&radii).map(|x| x.powi(3)).component_div(&radii).normalize();
+
+            assert!((radial_on_superellipsoid(closest, radii, 4.0) - closest).magnitude() < 1e-3);
+            assert!((closest - pos).magnitude() <= (radial - pos).magnitude() + 1e-4);
+            assert!((pos - closest).normalize().dot(&normal).abs() > 0.9999, "{:?} => {:?}", pos, closest);
+        }
+    }
+}
@@ -0,0 +1,172 @@
+use nalgebra::Vector4;
+use crate::manifold::implicit::ScalarField;
+
+/// The polynomial smooth minimum, which only differs from min where a and b are within smoothness of each other
+fn smooth_min(a: f32, b: f32, smoothness: f32) -> f32 {
+    let h = (0.5 + 0.5 * (b - a) / smoothness).clamp(0.0, 1.0);
+
+    b + (a - b) * h - smoothness * h * (1.0 - h)
+}
+
+const SMOOTH_MIN_WGSL: &str = "\
+    let h = clamp(0.5 + 0.5 * (b - a) / smoothness, 0.0, 1.0);\
+\n    return mix(b, a, h) - smoothness * h * (1.0 - h);\
+";
+
+// The same as -smooth_min(-a, -b)
+const SMOOTH_MAX_WGSL: &str = "\
+    let h = clamp(0.5 + 0.5 * (a - b) / smoothness, 0.0, 1.0);\
+\n    return mix(b, a, h) + smoothness * h * (1.0 - h);\
+";
+
+/// The wgsl for a field combining `{name}_a` and `{name}_b` with the given body using a and b
+fn combine_wgsl(name: &str, a: &impl ScalarField, b: &impl ScalarField, body: &str) -> String {
+    format!("\
+        {}\
+  \n\
+  \n{}\
+  \n\
+  \nfn {}(pos: vec4<f32>) -> f32 {{\
+  \n    let a = {}_a(pos);\
+  \n    let b = {}_b(pos);\
+  \n    {}\
+  \n}}\
+    ",
+        a.evaluate_wgsl(&format!("{}_a", name)),
+        b.evaluate_wgsl(&format!("{}_b", name)),
+        name, name, name,
+        body
+    )
+}
+
+/// Everything inside either field, its zero set is the outside of both shapes
+#[derive(Debug)]
+pub struct Union<A: ScalarField, B: ScalarField>(pub A, pub B);
+
+impl<A: ScalarField, B: ScalarField> ScalarField for Union<A, B> {
+    fn description(&self) -> String {
+        format!("({} or {})", self.0.description(), self.1.description())
+    }
+
+    fn evaluate_wgsl(&self, name: &str) -> String {
+        combine_wgsl(name, &self.0, &self.1, "return min(a, b);")
+    }
+
+    fn evaluate(&self, pos: Vector4<f32>) -> f32 {
+        self.0.evaluate(pos).min(self.1.evaluate(pos))
+    }
+}
+
+/// Everything inside both fields
+#[derive(Debug)]
+pub struct Intersection<A: ScalarField, B: ScalarField>(pub A, pub B);
+
+impl<A: ScalarField, B: ScalarField> ScalarField for Intersection<A, B> {
+    fn description(&self) -> String {
+        format!("({} and {})", self.0.description(), self.1.description())
+    }
+
+    fn evaluate_wgsl(&self, name: &str) -> String {
+        combine_wgsl(name, &self.0, &self.1, "return max(a, b);")
+    }
+
+    fn evaluate(&self, pos: Vector4<f32>) -> f32 {
+        self.0.evaluate(pos).max(self.1.evaluate(pos))
+    }
+}
+
+/// Everything inside the first field and outside the second
+#[derive(Debug)]
+pub struct Difference<A: ScalarField, B: ScalarField>(pub A, pub B);
+
+impl<A: ScalarField, B: ScalarField> ScalarField for Difference<A, B> {
+    fn description(&self) -> String {
+        format!("({} without {})", self.0.description(), self.1.description())
+    }
+
+    fn evaluate_wgsl(&self, name: &str) -> String {
+        combine_wgsl(name, &self.0, &self.1, "return max(a, -b);")
+    }
+
+    fn evaluate(&self, pos: Vector4<f32>) -> f32 {
+        self.0.evaluate(pos).max(-self.1.evaluate(pos))
+    }
+}
+
+/// A union that fills in the creases where the shapes meet, over about `smoothness` of distance
+#[derive(Debug)]
+pub struct SmoothUnion<A: ScalarField, B: ScalarField> {
+    a: A,
+    b: B,
+    smoothness: f32
+}
+
+impl<A: ScalarField, B: ScalarField> SmoothUnion<A, B> {
+    pub fn new(a: A, b: B, smoothness: f32) -> Self {
+        Self { a, b, smoothness }
+    }
+}
+
+impl<A: ScalarField, B: ScalarField> ScalarField for SmoothUnion<A, B> {
+    fn description(&self) -> String {
+        format!("({} smoothly or {})", self.a.description(), self.b.description())
+    }
+
+    fn evaluate_wgsl(&self, name: &str) -> String {
+        combine_wgsl(name, &self.a, &self.b, &format!(
+            "let smoothness = {:?};\n    {}", self.smoothness, SMOOTH_MIN_WGSL
+        ))
+    }
+
+    fn evaluate(&self, pos: Vector4<f32>) -> f32 {
+        smooth_min(self.a.evaluate(pos), self.b.evaluate(pos), self.smoothness)
+    }
+}
+
+/// An intersection that rounds off the edges where the shapes meet, over about `smoothness` of distance
+#[derive(Debug)]
+pub struct SmoothIntersection<A: ScalarField, B: ScalarField> {
+    a: A,
+    b: B,
+    smoothness: f32
+}
+
+impl<A: ScalarField, B: ScalarField> SmoothIntersection<A, B> {
+    pub fn new(a: A, b: B, smoothness: f32) -> Self {
+        Self { a, b, smoothness }
+    }
+}
+
+impl<A: ScalarField, B: ScalarField> ScalarField for SmoothIntersection<A, B> {
+    fn description(&self) -> String {
+        format!("({} smoothly and {})", self.a.description(), self.b.description())
+    }
+
+    fn evaluate_wgsl(&self, name: &str) -> String {
+        combine_wgsl(name, &self.a, &self.b, &format!(
+            "let smoothness = {:?};\n    {}", self.smoothness, SMOOTH_MAX_WGSL
+        ))
+    }
+
+    fn evaluate(&self, pos: Vector4<f32>) -> f32 {
+        -smooth_min(-self.a.evaluate(pos), -self.b.evaluate(pos), self.smoothness)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::manifold::shapes4D::{Hyperplane, Hypersphere};
+    use super::*;
+
+    #[test]
+    fn test_smooth_min() {
+        assert_eq!(smooth_min(1.0, 3.0, 0.5), 1.0);
+        assert_eq!(smooth_min(3.0, 1.0, 0.5), 1.0);
+        assert!(smooth_min(1.0, 1.0, 0.5) < 1.0);
+
+        let pos = Vector4::new(0.5, 0.0, 0.0, 0.1);
+        let union = SmoothUnion::new(Hyperplane, Hypersphere::new(2.0), 1.0);
+
+        assert!(union.evaluate(pos) <= Hyperplane.evaluate(pos).min(Hypersphere::new(2.0).evaluate(pos)));
+    }
+}
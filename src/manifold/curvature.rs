@@ -0,0 +1,133 @@
+use nalgebra::{Matrix3, Vector3, Vector4};
+use crate::manifold::Manifold;
+
+/// The distance used to differentiate the normal
+const CURVATURE_DELTA: f32 = 0.05;
+
+/// The shape operator dn of a manifold at a point, as a symmetric matrix in its tangent_basis. Every curvature of
+/// a hypersurface follows from it through the Gauss equation. It is taken with respect to normal_at, so numerical
+/// normals can flip the sign of the principal curvatures while the sectional, Ricci and scalar curvatures don't change.
+/// The Gauss equation only holds for hypersurfaces of euclidean space, so the intrinsic curvatures are wrong for
+/// manifolds defined by their own metric, like Hyperbolic which has -1 / radius² everywhere
+#[derive(Debug, Clone)]
+pub struct ShapeOperator {
+    pub pos: Vector4<f32>,
+    pub normal: Vector4<f32>,
+    pub basis: [Vector4<f32>; 3],
+    pub matrix: Matrix3<f32>
+}
+
+impl ShapeOperator {
+    /// Differentiates the normals at nearby points along each tangent basis vector
+    pub fn new<M: Manifold + ?Sized>(manifold: &M, pos: Vector4<f32>) -> Self {
+        let pos = manifold.project_onto(pos);
+        let normal = manifold.normal_at(pos);
+        let basis = manifold.tangent_basis(pos);
+
+        // Numerical normals are only defined up to sign so the nearby ones are flipped to match
+        let oriented_normal = |pos: Vector4<f32>| {
+            let nearby_normal = manifold.normal_at(pos);
+
+            if nearby_normal.dot(&normal) < 0.0 { -nearby_normal } else { nearby_normal }
+        };
+
+        let mut matrix = Matrix3::zeros();
+
+        for j in 0..3 {
+            let delta = CURVATURE_DELTA * basis[j];
+            let derivative = (oriented_normal(pos + delta) - oriented_normal(pos - delta)) / (2.0 * CURVATURE_DELTA);
+
+            for i in 0..3 {
+                matrix[(i, j)] = basis[i].dot(&derivative);
+            }
+        }
+
+        Self { pos, normal, basis, matrix: 0.5 * (matrix + matrix.transpose()) }
+    }
+
+    /// A tangent vector in the coordinates of the basis
+    fn coordinates(&self, vector: Vector4<f32>) -> Vector3<f32> {
+        Vector3::new(self.basis[0].dot(&vector), self.basis[1].dot(&vector), self.basis[2].dot(&vector))
+    }
+
+    /// The eigenvalues of the shape operator from smallest to largest
+    pub fn principal_curvatures(&self) -> [f32; 3] {
+        let mut curvatures: [f32; 3] = self.matrix.symmetric_eigenvalues().into();
+
+        curvatures.sort_by(f32::total_cmp);
+
+        curvatures
+    }
+
+    /// The mean of the principal curvatures
+    pub fn mean_curvature(&self) -> f32 {
+        self.matrix.trace() / 3.0
+    }
+
+    /// II(u, v), how fast the normal turns along u measured in the direction v
+    pub fn second_fundamental_form(&self, u: Vector4<f32>, v: Vector4<f32>) -> f32 {
+        self.coordinates(u).dot(&(self.matrix * self.coordinates(v)))
+    }
+
+    /// The sectional curvature of the tangent plane spanned by u and v, using the Gauss equation
+    pub fn sectional_curvature(&self, u: Vector4<f32>, v: Vector4<f32>) -> f32 {
+        let (u, v) = (self.coordinates(u), self.coordinates(v));
+        let area_squared = u.magnitude_squared() * v.magnitude_squared() - u.dot(&v).powi(2);
+        let (uu, vv, uv) = (
+            u.dot(&(self.matrix * u)),
+            v.dot(&(self.matrix * v)),
+            u.dot(&(self.matrix * v))
+        );
+
+        (uu * vv - uv.powi(2)) / area_squared
+    }
+
+    /// The Ricci tensor in the coordinates of the basis, for a hypersurface it is trace(S) S - S²
+    pub fn ricci_curvature(&self) -> Matrix3<f32> {
+        self.matrix.trace() * self.matrix - self.matrix * self.matrix
+    }
+
+    /// The trace of the Ricci tensor, twice the sum of the sectional curvatures of the basis planes
+    pub fn scalar_curvature(&self) -> f32 {
+        self.ricci_curvature().trace()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::manifold::shapes4D::{Hyperplane, Hypersphere, TigerPairing};
+    use crate::manifold::shapes2D::{Circle, Plane};
+    use crate::manifold::product::Product;
+    use super::*;
+
+    #[test]
+    fn test_known_curvatures() {
+        let sphere = Hypersphere::new(3.0);
+
+        for pos in [Vector4::zeros(), Vector4::new(1.0, -2.0, 0.5, 4.0), Vector4::new(-3.0, 0.0, 2.0, 1.0)] {
+            let shape = ShapeOperator::new(&sphere, pos);
+            let [u, v, w] = shape.basis;
+
+            for curvature in shape.principal_curvatures() {
+                assert!((curvature - 1.0 / 3.0).abs() < 1e-3);
+            }
+
+            assert!((shape.sectional_curvature(u, v + 0.5 * w) - 1.0 / 9.0).abs() < 1e-3);
+            assert!((shape.ricci_curvature() - Matrix3::identity() * 2.0 / 9.0).abs().max() < 1e-3);
+            assert!((shape.scalar_curvature() - 6.0 / 9.0).abs() < 1e-3);
+        }
+
+        let shape = ShapeOperator::new(&Hyperplane, Vector4::new(1.0, 2.0, 3.0, 0.5));
+
+        assert_eq!(shape.principal_curvatures(), [0.0; 3]);
+        assert_eq!(shape.scalar_curvature(), 0.0);
+
+        // A cylinder over a circle is flat, even though one principal curvature isn't zero
+        let cylinder = Product::new(Circle::new(3.0), Plane, TigerPairing::XwZy);
+        let shape = ShapeOperator::new(&cylinder, Vector4::new(1.0, 2.0, 3.0, 0.5));
+        let [_, middle, largest] = shape.principal_curvatures().map(f32::abs);
+
+        assert!(middle < 1e-3 && (largest - 1.0 / 3.0).abs() < 1e-2);
+        assert!(shape.scalar_curvature().abs() < 1e-3);
+    }
+}
@@ -0,0 +1,849 @@
+use std::fmt;
+use std::ops::{Add, Div, Mul, Neg, Sub};
+use nalgebra::{DVector, SVector, Vector3, Vector4};
+use crate::manifold::{EPSILON, Manifold};
+
+const COMPONENTS: [char; 4] = ['x', 'y', 'z', 'w'];
+
+/// How far projections are nudged off the points where they divide by zero
+const NUDGE: f32 = 0.0001;
+
+/// The uniforms of ManifoldInfo an expression can read
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Param {
+    V1,
+    V2,
+    V3,
+    R1,
+    R2,
+    R3
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UnaryOp {
+    Neg,
+    Abs,
+    Sqrt,
+    Sin,
+    Cos,
+    Tan,
+    Exp,
+    Log,
+    Floor,
+    Length,
+    Normalize
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Pow,
+    Min,
+    Max,
+    Atan2,
+    Dot
+}
+
+/// A formula that can both be evaluated on the cpu and turned into wgsl, so the two can't drift apart.
+/// `Position` is `pos` in the wgsl, a vec3 for Shape3Ds and a vec4 for Manifolds.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Constant(f32),
+    Position,
+    Param(Param),
+    Variable(String),
+    Let(String, Box<Expr>, Box<Expr>),
+    Vector(Vec<Expr>),
+    Swizzle(Box<Expr>, Vec<usize>),
+    Unary(UnaryOp, Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Type {
+    Scalar,
+    Vector(usize)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Scalar(f32),
+    Vector(DVector<f32>)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExprError {
+    UnknownVariable(String),
+    DuplicateVariable(String),
+    InvalidVariableName(String),
+    InvalidSwizzle(Type, Vec<usize>),
+    InvalidVectorSize(usize),
+    UnaryTypeMismatch(UnaryOp, Type),
+    BinaryTypeMismatch(BinaryOp, Type, Type),
+    UnexpectedType { expected: Type, found: Type }
+}
+
+impl fmt::Display for ExprError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownVariable(name) => write!(f, "unknown variable `{}`", name),
+            Self::DuplicateVariable(name) => write!(f, "variable `{}` is bound more than once", name),
+            Self::InvalidVariableName(name) => write!(f, "`{}` can't be used as a variable name", name),
+            Self::InvalidSwizzle(ty, components) => write!(f, "can't swizzle {:?} with {:?}", ty, components),
+            Self::InvalidVectorSize(size) => write!(f, "vectors must have 2 to 4 components, not {}", size),
+            Self::UnaryTypeMismatch(op, ty) => write!(f, "{:?} can't be applied to {:?}", op, ty),
+            Self::BinaryTypeMismatch(op, a, b) => write!(f, "{:?} can't be applied to {:?} and {:?}", op, a, b),
+            Self::UnexpectedType { expected, found } => write!(f, "expected {:?}, found {:?}", expected, found)
+        }
+    }
+}
+
+impl std::error::Error for ExprError {}
+
+/// The values behind each Param, laid out the same as ManifoldInfo
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Params {
+    pub vectors: [Vector4<f32>; 3],
+    pub radii: [f32; 3]
+}
+
+impl Params {
+    /// Reads the params back out of bytes from `Manifold::get_bytes`
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        let mut floats = [0.0; 15];
+
+        for (float, chunk) in floats.iter_mut().zip(bytes.chunks_exact(4)) {
+            *float = f32::from_ne_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        }
+
+        Self {
+            vectors: [
+                Vector4::from_column_slice(&floats[0..4]),
+                Vector4::from_column_slice(&floats[4..8]),
+                Vector4::from_column_slice(&floats[8..12])
+            ],
+            radii: [floats[12], floats[13], floats[14]]
+        }
+    }
+
+    pub fn from_shape_data(
+        (v1, v2, v3, r1, r2, r3): (Vector3<f32>, Vector3<f32>, Vector3<f32>, f32, f32, f32)
+    ) -> Self {
+        Self {
+            vectors: [
+                v1.fixed_resize::<4, 1>(0.0),
+                v2.fixed_resize::<4, 1>(0.0),
+                v3.fixed_resize::<4, 1>(0.0)
+            ],
+            radii: [r1, r2, r3]
+        }
+    }
+
+    pub fn get_bytes(&self) -> Vec<u8> {
+        bytemuck::cast_slice(&[
+            self.vectors[0].as_slice(),
+            self.vectors[1].as_slice(),
+            self.vectors[2].as_slice(),
+            &self.radii
+        ].concat()[..]).to_owned()
+    }
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Self {
+            vectors: [Vector4::zeros(); 3],
+            radii: [0.0; 3]
+        }
+    }
+}
+
+impl Value {
+    pub fn get_type(&self) -> Type {
+        match self {
+            Self::Scalar(_) => Type::Scalar,
+            Self::Vector(vector) => Type::Vector(vector.len())
+        }
+    }
+
+    pub fn as_scalar(&self) -> Result<f32, ExprError> {
+        match self {
+            Self::Scalar(scalar) => Ok(*scalar),
+            _ => Err(ExprError::UnexpectedType { expected: Type::Scalar, found: self.get_type() })
+        }
+    }
+
+    pub fn as_vector4(&self) -> Result<Vector4<f32>, ExprError> {
+        match self {
+            Self::Vector(vector) if vector.len() == 4 => Ok(Vector4::from_column_slice(vector.as_slice())),
+            _ => Err(ExprError::UnexpectedType { expected: Type::Vector(4), found: self.get_type() })
+        }
+    }
+
+    pub fn as_vector3(&self) -> Result<Vector3<f32>, ExprError> {
+        match self {
+            Self::Vector(vector) if vector.len() == 3 => Ok(Vector3::from_column_slice(vector.as_slice())),
+            _ => Err(ExprError::UnexpectedType { expected: Type::Vector(3), found: self.get_type() })
+        }
+    }
+
+    fn map(self, f: impl Fn(f32) -> f32) -> Self {
+        match self {
+            Self::Scalar(scalar) => Self::Scalar(f(scalar)),
+            Self::Vector(vector) => Self::Vector(vector.map(f))
+        }
+    }
+}
+
+impl UnaryOp {
+    fn wgsl_name(&self) -> &'static str {
+        match self {
+            Self::Neg => "-",
+            Self::Abs => "abs",
+            Self::Sqrt => "sqrt",
+            Self::Sin => "sin",
+            Self::Cos => "cos",
+            Self::Tan => "tan",
+            Self::Exp => "exp",
+            Self::Log => "log",
+            Self::Floor => "floor",
+            Self::Length => "length",
+            Self::Normalize => "normalize"
+        }
+    }
+
+    fn get_type(&self, ty: Type) -> Result<Type, ExprError> {
+        match (self, ty) {
+            (Self::Length, _) => Ok(Type::Scalar),
+            (Self::Normalize, Type::Scalar) => Err(ExprError::UnaryTypeMismatch(*self, ty)),
+            _ => Ok(ty)
+        }
+    }
+
+    fn apply(&self, value: Value) -> Value {
+        match self {
+            Self::Neg => value.map(|a| -a),
+            Self::Abs => value.map(f32::abs),
+            Self::Sqrt => value.map(f32::sqrt),
+            Self::Sin => value.map(f32::sin),
+            Self::Cos => value.map(f32::cos),
+            Self::Tan => value.map(f32::tan),
+            Self::Exp => value.map(f32::exp),
+            Self::Log => value.map(f32::ln),
+            Self::Floor => value.map(f32::floor),
+            Self::Length => match value {
+                Value::Scalar(scalar) => Value::Scalar(scalar.abs()),
+                Value::Vector(vector) => Value::Scalar(vector.magnitude())
+            },
+            Self::Normalize => match value {
+                Value::Vector(vector) => Value::Vector(&vector / vector.magnitude()),
+                scalar => scalar
+            }
+        }
+    }
+}
+
+impl BinaryOp {
+    fn wgsl_name(&self) -> &'static str {
+        match self {
+            Self::Add => "+",
+            Self::Sub => "-",
+            Self::Mul => "*",
+            Self::Div => "/",
+            Self::Pow => "pow",
+            Self::Min => "min",
+            Self::Max => "max",
+            Self::Atan2 => "atan2",
+            Self::Dot => "dot"
+        }
+    }
+
+    fn is_infix(&self) -> bool {
+        matches!(self, Self::Add | Self::Sub | Self::Mul | Self::Div)
+    }
+
+    fn get_type(&self, a: Type, b: Type) -> Result<Type, ExprError> {
+        match (self, a, b) {
+            (Self::Dot, Type::Vector(n), Type::Vector(m)) if n == m => Ok(Type::Scalar),
+            (Self::Dot, _, _) => Err(ExprError::BinaryTypeMismatch(*self, a, b)),
+            (_, a, b) if a == b => Ok(a),
+            (op, Type::Scalar, Type::Vector(n)) | (op, Type::Vector(n), Type::Scalar) if op.is_infix() =>
+                Ok(Type::Vector(n)),
+            _ => Err(ExprError::BinaryTypeMismatch(*self, a, b))
+        }
+    }
+
+    fn apply_scalar(&self, a: f32, b: f32) -> f32 {
+        match self {
+            Self::Add => a + b,
+            Self::Sub => a - b,
+            Self::Mul => a * b,
+            Self::Div => a / b,
+            Self::Pow => a.powf(b),
+            Self::Min => a.min(b),
+            Self::Max => a.max(b),
+            Self::Atan2 => a.atan2(b),
+            Self::Dot => a * b
+        }
+    }
+
+    fn apply(&self, a: Value, b: Value) -> Result<Value, ExprError> {
+        self.get_type(a.get_type(), b.get_type())?;
+
+        Ok(match (self, a, b) {
+            (Self::Dot, Value::Vector(a), Value::Vector(b)) => Value::Scalar(a.dot(&b)),
+            (_, Value::Scalar(a), Value::Scalar(b)) => Value::Scalar(self.apply_scalar(a, b)),
+            (_, Value::Scalar(a), Value::Vector(b)) => Value::Vector(b.map(|b| self.apply_scalar(a, b))),
+            (_, Value::Vector(a), Value::Scalar(b)) => Value::Vector(a.map(|a| self.apply_scalar(a, b))),
+            (_, Value::Vector(a), Value::Vector(b)) => Value::Vector(a.zip_map(&b, |a, b| self.apply_scalar(a, b)))
+        })
+    }
+}
+
+impl Param {
+    fn wgsl_name(&self, dimension: usize) -> String {
+        let name = match self {
+            Self::V1 => "v1",
+            Self::V2 => "v2",
+            Self::V3 => "v3",
+            Self::R1 => "r1",
+            Self::R2 => "r2",
+            Self::R3 => "r3"
+        };
+
+        match (self, dimension) {
+            (Self::V1 | Self::V2 | Self::V3, 3) => format!("manifold_info.{}.xyz", name),
+            _ => format!("manifold_info.{}", name)
+        }
+    }
+
+    fn get_type(&self, dimension: usize) -> Type {
+        match self {
+            Self::V1 | Self::V2 | Self::V3 => Type::Vector(dimension),
+            _ => Type::Scalar
+        }
+    }
+
+    fn get_value(&self, params: &Params, dimension: usize) -> Value {
+        match self {
+            Self::V1 => Value::Vector(DVector::from_column_slice(&params.vectors[0].as_slice()[..dimension])),
+            Self::V2 => Value::Vector(DVector::from_column_slice(&params.vectors[1].as_slice()[..dimension])),
+            Self::V3 => Value::Vector(DVector::from_column_slice(&params.vectors[2].as_slice()[..dimension])),
+            Self::R1 => Value::Scalar(params.radii[0]),
+            Self::R2 => Value::Scalar(params.radii[1]),
+            Self::R3 => Value::Scalar(params.radii[2])
+        }
+    }
+}
+
+impl Expr {
+    pub fn pos() -> Self {
+        Self::Position
+    }
+
+    pub fn constant(value: f32) -> Self {
+        Self::Constant(value)
+    }
+
+    pub fn param(param: Param) -> Self {
+        Self::Param(param)
+    }
+
+    pub fn var(name: &str) -> Self {
+        Self::Variable(name.to_string())
+    }
+
+    /// Binds `value` to `name` inside of `body`, variable names have to be unique within an expression
+    pub fn let_in(name: &str, value: Expr, body: Expr) -> Self {
+        Self::Let(name.to_string(), Box::new(value), Box::new(body))
+    }
+
+    pub fn vector(items: Vec<Expr>) -> Self {
+        Self::Vector(items)
+    }
+
+    pub fn unary(op: UnaryOp, a: Expr) -> Self {
+        Self::Unary(op, Box::new(a))
+    }
+
+    pub fn binary(op: BinaryOp, a: Expr, b: Expr) -> Self {
+        Self::Binary(op, Box::new(a), Box::new(b))
+    }
+
+    /// Takes components like wgsl does, ex: `pos.swizzle("xw")`
+    pub fn swizzle(self, components: &str) -> Self {
+        Self::Swizzle(Box::new(self), components.chars()
+            .map(|c| COMPONENTS.iter()
+                .position(|component| *component == c)
+                .unwrap_or_else(|| panic!("`{}` is not a component", c)))
+            .collect())
+    }
+
+    pub fn x(self) -> Self { self.swizzle("x") }
+    pub fn y(self) -> Self { self.swizzle("y") }
+    pub fn z(self) -> Self { self.swizzle("z") }
+    pub fn w(self) -> Self { self.swizzle("w") }
+
+    pub fn abs(self) -> Self { Self::unary(UnaryOp::Abs, self) }
+    pub fn sqrt(self) -> Self { Self::unary(UnaryOp::Sqrt, self) }
+    pub fn length(self) -> Self { Self::unary(UnaryOp::Length, self) }
+    pub fn normalize(self) -> Self { Self::unary(UnaryOp::Normalize, self) }
+
+    pub fn pow(self, exponent: Expr) -> Self { Self::binary(BinaryOp::Pow, self, exponent) }
+    pub fn min(self, other: Expr) -> Self { Self::binary(BinaryOp::Min, self, other) }
+    pub fn max(self, other: Expr) -> Self { Self::binary(BinaryOp::Max, self, other) }
+    pub fn dot(self, other: Expr) -> Self { Self::binary(BinaryOp::Dot, self, other) }
+
+    /// Whether the expression reads any of the ManifoldInfo uniforms
+    pub fn uses_params(&self) -> bool {
+        match self {
+            Self::Param(_) => true,
+            Self::Let(_, a, b) | Self::Binary(_, a, b) => a.uses_params() || b.uses_params(),
+            Self::Vector(items) => items.iter().any(|item| item.uses_params()),
+            Self::Swizzle(a, _) | Self::Unary(_, a) => a.uses_params(),
+            _ => false
+        }
+    }
+
+    /// Type checks the expression where `pos` has `dimension` components
+    pub fn get_type(&self, dimension: usize) -> Result<Type, ExprError> {
+        let mut names = Vec::new();
+        self.check_names(&mut names)?;
+
+        self.get_type_in(dimension, &mut Vec::new())
+    }
+
+    fn check_names(&self, names: &mut Vec<String>) -> Result<(), ExprError> {
+        match self {
+            Self::Let(name, value, body) => {
+                let is_identifier = name.chars().next().is_some_and(|c| c.is_ascii_alphabetic()) &&
+                    name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+
+                if !is_identifier || name == "pos" || name == "manifold_info" {
+                    return Err(ExprError::InvalidVariableName(name.clone()));
+                } else if names.contains(name) {
+                    return Err(ExprError::DuplicateVariable(name.clone()));
+                }
+
+                names.push(name.clone());
+                value.check_names(names)?;
+                body.check_names(names)
+            },
+            Self::Vector(items) => items.iter().try_for_each(|item| item.check_names(names)),
+            Self::Swizzle(a, _) | Self::Unary(_, a) => a.check_names(names),
+            Self::Binary(_, a, b) => {
+                a.check_names(names)?;
+                b.check_names(names)
+            },
+            _ => Ok(())
+        }
+    }
+
+    fn get_type_in(&self, dimension: usize, scope: &mut Vec<(String, Type)>) -> Result<Type, ExprError> {
+        match self {
+            Self::Constant(_) => Ok(Type::Scalar),
+            Self::Position => Ok(Type::Vector(dimension)),
+            Self::Param(param) => Ok(param.get_type(dimension)),
+            Self::Variable(name) => scope.iter()
+                .rev()
+                .find(|(scope_name, _)| scope_name == name)
+                .map(|(_, ty)| *ty)
+                .ok_or_else(|| ExprError::UnknownVariable(name.clone())),
+            Self::Let(name, value, body) => {
+                let ty = value.get_type_in(dimension, scope)?;
+
+                scope.push((name.clone(), ty));
+                let body_ty = body.get_type_in(dimension, scope);
+                scope.pop();
+
+                body_ty
+            },
+            Self::Vector(items) => {
+                let mut size = 0;
+
+                for item in items {
+                    size += match item.get_type_in(dimension, scope)? {
+                        Type::Scalar => 1,
+                        Type::Vector(n) => n
+                    };
+                }
+
+                if (2..=4).contains(&size) {
+                    Ok(Type::Vector(size))
+                } else {
+                    Err(ExprError::InvalidVectorSize(size))
+                }
+            },
+            Self::Swizzle(a, components) => match a.get_type_in(dimension, scope)? {
+                Type::Vector(n) if components.iter().all(|i| *i < n) => match components.len() {
+                    1 => Ok(Type::Scalar),
+                    2..=4 => Ok(Type::Vector(components.len())),
+                    _ => Err(ExprError::InvalidSwizzle(Type::Vector(n), components.clone()))
+                },
+                ty => Err(ExprError::InvalidSwizzle(ty, components.clone()))
+            },
+            Self::Unary(op, a) => op.get_type(a.get_type_in(dimension, scope)?),
+            Self::Binary(op, a, b) => op.get_type(
+                a.get_type_in(dimension, scope)?,
+                b.get_type_in(dimension, scope)?
+            )
+        }
+    }
+
+    /// Evaluates the expression on the cpu with `pos` as the position
+    pub fn evaluate(&self, pos: &[f32], params: &Params) -> Result<Value, ExprError> {
+        self.get_type(pos.len())?;
+
+        self.evaluate_in(&DVector::from_column_slice(pos), params, &mut Vec::new())
+    }
+
+    fn evaluate_in(
+        &self,
+        pos: &DVector<f32>,
+        params: &Params,
+        scope: &mut Vec<(String, Value)>
+    ) -> Result<Value, ExprError> {
+        match self {
+            Self::Constant(value) => Ok(Value::Scalar(*value)),
+            Self::Position => Ok(Value::Vector(pos.clone())),
+            Self::Param(param) => Ok(param.get_value(params, pos.len())),
+            Self::Variable(name) => scope.iter()
+                .rev()
+                .find(|(scope_name, _)| scope_name == name)
+                .map(|(_, value)| value.clone())
+                .ok_or_else(|| ExprError::UnknownVariable(name.clone())),
+            Self::Let(name, value, body) => {
+                let value = value.evaluate_in(pos, params, scope)?;
+
+                scope.push((name.clone(), value));
+                let body_value = body.evaluate_in(pos, params, scope);
+                scope.pop();
+
+                body_value
+            },
+            Self::Vector(items) => {
+                let mut components = Vec::new();
+
+                for item in items {
+                    match item.evaluate_in(pos, params, scope)? {
+                        Value::Scalar(scalar) => components.push(scalar),
+                        Value::Vector(vector) => components.extend(vector.iter())
+                    }
+                }
+
+                Ok(Value::Vector(DVector::from_vec(components)))
+            },
+            Self::Swizzle(a, components) => match a.evaluate_in(pos, params, scope)? {
+                Value::Vector(vector) if components.len() == 1 => Ok(Value::Scalar(vector[components[0]])),
+                Value::Vector(vector) => Ok(Value::Vector(DVector::from_iterator(
+                    components.len(),
+                    components.iter().map(|i| vector[*i])
+                ))),
+                value => Err(ExprError::InvalidSwizzle(value.get_type(), components.clone()))
+            },
+            Self::Unary(op, a) => Ok(op.apply(a.evaluate_in(pos, params, scope)?)),
+            Self::Binary(op, a, b) => op.apply(
+                a.evaluate_in(pos, params, scope)?,
+                b.evaluate_in(pos, params, scope)?
+            )
+        }
+    }
+
+    /// The body of a wgsl function with a `pos` parameter that returns this expression
+    pub fn to_wgsl(&self, dimension: usize) -> Result<String, ExprError> {
+        self.get_type(dimension)?;
+
+        let mut statements = Vec::new();
+        let result = self.to_wgsl_in(dimension, &mut statements, &mut Vec::new())?;
+        statements.push(format!("return {};", result));
+
+        Ok(statements.join("\n    "))
+    }
+
+    /// The body of a projection fn taking `pos`, where this expression gives the projected point. Like `nudged` it
+    /// projects again from pos + NUDGE when the result isn't finite, checking the exponent bits since the shader
+    /// compiler is allowed to assume there are no NaNs
+    pub fn projection_wgsl(&self, dimension: usize) -> Result<String, ExprError> {
+        match self.get_type(dimension)? {
+            Type::Vector(n) if n == dimension => Ok(format!("\
+                    let projected = projection_{0}d(pos);\
+              \n    let exponents = bitcast<vec{0}<u32>>(projected) & vec{0}(0x7f800000u);\
+              \n    \
+              \n    if (any(exponents == vec{0}(0x7f800000u))) {{\
+              \n        return projection_{0}d(pos + {1:?});\
+              \n    }}\
+              \n    \
+              \n    return projected;\
+              \n}}\
+              \n\
+              \nfn projection_{0}d(pos: vec{0}<f32>) -> vec{0}<f32> {{\
+              \n    {2}\
+                ", dimension, NUDGE, self.to_wgsl(dimension)?)),
+            found => Err(ExprError::UnexpectedType { expected: Type::Vector(dimension), found })
+        }
+    }
+
+    // Lets get hoisted into statements, which is fine since every variable name is unique
+    fn to_wgsl_in(
+        &self,
+        dimension: usize,
+        statements: &mut Vec<String>,
+        scope: &mut Vec<(String, Type)>
+    ) -> Result<String, ExprError> {
+        Ok(match self {
+            Self::Constant(value) => format!("{:?}", value),
+            Self::Position => String::from("pos"),
+            Self::Param(param) => param.wgsl_name(dimension),
+            Self::Variable(name) => name.clone(),
+            Self::Let(name, value, body) => {
+                let ty = value.get_type_in(dimension, scope)?;
+                let value = value.to_wgsl_in(dimension, statements, scope)?;
+                statements.push(format!("let {} = {};", name, value));
+
+                scope.push((name.clone(), ty));
+                let body = body.to_wgsl_in(dimension, statements, scope);
+                scope.pop();
+
+                body?
+            },
+            Self::Vector(items) => {
+                let size = match self.get_type_in(dimension, scope)? {
+                    Type::Vector(n) => n,
+                    Type::Scalar => 1
+                };
+                let items = items.iter()
+                    .map(|item| item.to_wgsl_in(dimension, statements, scope))
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                format!("vec{}<f32>({})", size, items.join(", "))
+            },
+            Self::Swizzle(a, components) => {
+                let a = a.to_wgsl_in(dimension, statements, scope)?;
+                let components = components.iter().map(|i| COMPONENTS[*i]).collect::<String>();
+
+                if a.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.') {
+                    format!("{}.{}", a, components)
+                } else {
+                    format!("({}).{}", a, components)
+                }
+            },
+            Self::Unary(UnaryOp::Neg, a) => format!("(-({}))", a.to_wgsl_in(dimension, statements, scope)?),
+            Self::Unary(op, a) => format!("{}({})", op.wgsl_name(), a.to_wgsl_in(dimension, statements, scope)?),
+            Self::Binary(op, a, b) => {
+                let a = a.to_wgsl_in(dimension, statements, scope)?;
+                let b = b.to_wgsl_in(dimension, statements, scope)?;
+
+                if op.is_infix() {
+                    format!("({} {} {})", a, op.wgsl_name(), b)
+                } else {
+                    format!("{}({}, {})", op.wgsl_name(), a, b)
+                }
+            }
+        })
+    }
+}
+
+impl From<f32> for Expr {
+    fn from(value: f32) -> Self {
+        Self::Constant(value)
+    }
+}
+
+impl Add for Expr {
+    type Output = Expr;
+
+    fn add(self, other: Expr) -> Expr {
+        Expr::binary(BinaryOp::Add, self, other)
+    }
+}
+
+impl Sub for Expr {
+    type Output = Expr;
+
+    fn sub(self, other: Expr) -> Expr {
+        Expr::binary(BinaryOp::Sub, self, other)
+    }
+}
+
+impl Mul for Expr {
+    type Output = Expr;
+
+    fn mul(self, other: Expr) -> Expr {
+        Expr::binary(BinaryOp::Mul, self, other)
+    }
+}
+
+impl Div for Expr {
+    type Output = Expr;
+
+    fn div(self, other: Expr) -> Expr {
+        Expr::binary(BinaryOp::Div, self, other)
+    }
+}
+
+impl Neg for Expr {
+    type Output = Expr;
+
+    fn neg(self) -> Expr {
+        Expr::unary(UnaryOp::Neg, self)
+    }
+}
+
+impl Mul<Expr> for f32 {
+    type Output = Expr;
+
+    fn mul(self, other: Expr) -> Expr {
+        Expr::Constant(self) * other
+    }
+}
+
+/// Projects pos again nudged by NUDGE when it lands on a point where the projection divides by zero, the cpu side
+/// of the nudge in `Expr::projection_wgsl`. Closed form projections that have a projection_expr go through it so the
+/// two agree at those points too
+pub(crate) fn nudged<const D: usize>(pos: SVector<f32, D>, project: impl Fn(SVector<f32, D>) -> SVector<f32, D>) -> SVector<f32, D> {
+    let new_pos = project(pos);
+
+    if new_pos.iter().all(|c| c.is_finite()) {
+        new_pos
+    } else {
+        project(pos.add_scalar(NUDGE))
+    }
+}
+
+/// A manifold defined by a single projection expression, used for both `project_onto` and the wgsl
+#[derive(Debug)]
+pub struct ExprManifold {
+    description: String,
+    projection: Expr,
+    params: Params
+}
+
+impl ExprManifold {
+    pub fn new(description: &str, projection: Expr, params: Params) -> Result<Self, ExprError> {
+        match projection.get_type(4)? {
+            Type::Vector(4) => Ok(Self {
+                description: description.to_string(),
+                projection,
+                params
+            }),
+            found => Err(ExprError::UnexpectedType { expected: Type::Vector(4), found })
+        }
+    }
+
+    pub fn params_mut(&mut self) -> &mut Params {
+        &mut self.params
+    }
+}
+
+impl Manifold for ExprManifold {
+    fn description(&self) -> String {
+        self.description.clone()
+    }
+
+    fn project_onto_wgsl(&self) -> String {
+        self.projection.projection_wgsl(4).expect("Projection was type checked in ExprManifold::new")
+    }
+
+    fn project_onto(&self, pos: Vector4<f32>) -> Vector4<f32> {
+        nudged(pos, |pos| self.projection.evaluate(pos.as_slice(), &self.params)
+            .and_then(|value| value.as_vector4())
+            .expect("Projection was type checked in ExprManifold::new"))
+    }
+
+    fn is_on_curve(&self, pos: Vector4<f32>) -> bool {
+        (self.project_onto(pos) - pos).magnitude() < EPSILON
+    }
+
+    fn get_bytes(&self) -> Vec<u8> {
+        self.params.get_bytes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::manifold::shapes3D::*;
+    use crate::manifold::shapes4D::*;
+    use super::*;
+
+    /// Every point of the grid with spacing 1 around center, which hits the axes and centers where projections
+    /// divide by zero
+    fn grid<const D: usize>(center: SVector<f32, D>) -> impl Iterator<Item = SVector<f32, D>> {
+        (0..7usize.pow(D as u32)).map(move |index| {
+            center + SVector::<f32, D>::from_fn(|i, _| (index / 7usize.pow(i as u32) % 7) as f32 - 3.0)
+        })
+    }
+
+    #[test]
+    fn test_expressions_match_manifolds() {
+        let manifolds: Vec<Box<dyn Manifold>> = vec![
+            Box::new(Hyperplane),
+            Box::new(Hypersphere::new(3.0)),
+            Box::new(Hypersphube::new(3.0, 6.0)),
+            Box::new(Ditorus::new(4.0, 2.0, 1.0)),
+            Box::new(Tiger::new(4.0, 3.0, 1.0, TigerPairing::XyZw, true)),
+            Box::new(Tiger::new(4.0, 3.0, 1.0, TigerPairing::XzWy, false)),
+            Box::new(Tiger::new(4.0, 3.0, 1.0, TigerPairing::XwZy, true)),
+        ];
+
+        for manifold in manifolds {
+            let expr = manifold.projection_expr().unwrap();
+            let params = Params::from_bytes(&manifold.get_bytes());
+
+            for pos in grid(params.vectors[0]) {
+                let expr_pos = nudged(pos, |pos| expr.evaluate(pos.as_slice(), &params).and_then(|value| value.as_vector4()).unwrap());
+
+                assert!(
+                    (manifold.project_onto(pos) - expr_pos).magnitude() < EPSILON,
+                    "{}: {:?} => {:?} vs {:?}", manifold.description(), pos, manifold.project_onto(pos), expr_pos
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_expressions_match_shapes() {
+        let shapes: Vec<Box<dyn Shape3D>> = vec![
+            Box::new(Sphere::new(3.0)),
+            Box::new(Torus::new(4.0, 1.0)),
+        ];
+
+        for shape in shapes {
+            let expr = shape.projection_expr().unwrap();
+            let params = Params::from_shape_data(shape.get_data());
+
+            for pos in grid(params.vectors[0].xyz()) {
+                let expr_pos = nudged(pos, |pos| expr.evaluate(pos.as_slice(), &params).and_then(|value| value.as_vector3()).unwrap());
+
+                assert!(
+                    (shape.project_onto(pos) - expr_pos).magnitude() < EPSILON,
+                    "{}: {:?} => {:?} vs {:?}", shape.description(), pos, shape.project_onto(pos), expr_pos
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_negating_negative_constants() {
+        // --1.0 would be the decrement token
+        assert_eq!((-Expr::constant(-1.0)).to_wgsl(4), Ok(String::from("return (-(-1.0));")));
+    }
+
+    #[test]
+    fn test_type_errors() {
+        assert_eq!(
+            (Expr::pos() + Expr::pos().swizzle("xy")).get_type(4),
+            Err(ExprError::BinaryTypeMismatch(BinaryOp::Add, Type::Vector(4), Type::Vector(2)))
+        );
+        assert_eq!(
+            Expr::var("a").get_type(4),
+            Err(ExprError::UnknownVariable(String::from("a")))
+        );
+        assert_eq!(
+            Expr::let_in("a", Expr::constant(1.0), Expr::let_in("a", Expr::constant(2.0), Expr::var("a"))).get_type(4),
+            Err(ExprError::DuplicateVariable(String::from("a")))
+        );
+    }
+}
@@ -0,0 +1,323 @@
+use nalgebra::{Vector3, Vector4};
+use crate::manifold::{EPSILON, Manifold};
+
+const GRADIENT_DELTA: f32 = 0.001;
+const PROJECTION_ITERATIONS: u32 = 8;
+
+/// A function h(x, y, z) giving the w coordinate of a Heightfield
+pub trait HeightFunction: std::fmt::Debug {
+    fn description(&self) -> String {
+        String::from("Height function")
+    }
+
+    /// A full wgsl definition of `fn {name}(pos: vec3<f32>) -> f32`, any helper functions should start with name
+    fn evaluate_wgsl(&self, name: &str) -> String;
+
+    fn evaluate(&self, pos: Vector3<f32>) -> f32;
+
+    fn gradient(&self, pos: Vector3<f32>) -> Vector3<f32> {
+        let mut gradient = Vector3::zeros();
+
+        for i in 0..3 {
+            let mut delta = Vector3::zeros();
+            delta[i] = GRADIENT_DELTA;
+
+            gradient[i] = (self.evaluate(pos + delta) - self.evaluate(pos - delta)) / (2.0 * GRADIENT_DELTA);
+        }
+
+        gradient
+    }
+}
+
+/// A height function from a rust closure along with the body of the matching wgsl function
+pub struct FnHeight<F: Fn(Vector3<f32>) -> f32> {
+    function: F,
+    function_wgsl: String
+}
+
+impl<F: Fn(Vector3<f32>) -> f32> FnHeight<F> {
+    pub fn new(function: F, function_wgsl: &str) -> Self {
+        Self { function, function_wgsl: function_wgsl.to_string() }
+    }
+}
+
+impl<F: Fn(Vector3<f32>) -> f32> std::fmt::Debug for FnHeight<F> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FnHeight")
+            .field("function_wgsl", &self.function_wgsl)
+            .finish()
+    }
+}
+
+impl<F: Fn(Vector3<f32>) -> f32> HeightFunction for FnHeight<F> {
+    fn evaluate_wgsl(&self, name: &str) -> String {
+        format!("\
+            fn {}(pos: vec3<f32>) -> f32 {{\
+      \n    {}\
+      \n}}\
+        ", name, self.function_wgsl)
+    }
+
+    fn evaluate(&self, pos: Vector3<f32>) -> f32 {
+        (self.function)(pos)
+    }
+}
+
+/// Smooth value noise summed over octaves, each octave has double the frequency and half the amplitude of the last.
+/// The hash only uses u32 arithmetic so the cpu and wgsl versions give the same terrain for the same seed.
+#[derive(Debug, Clone, Copy)]
+pub struct Noise {
+    seed: u32,
+    octaves: u32,
+    amplitude: f32,
+    frequency: f32
+}
+
+impl Noise {
+    pub fn new(seed: u32, octaves: u32, amplitude: f32, frequency: f32) -> Self {
+        Self { seed, octaves, amplitude, frequency }
+    }
+
+    /// A value in [-1, 1] for each lattice point
+    fn hash(cell: Vector3<i32>, seed: u32) -> f32 {
+        let mut hash = seed ^
+            (cell.x as u32).wrapping_mul(0x8da6b343) ^
+            (cell.y as u32).wrapping_mul(0xd8163841) ^
+            (cell.z as u32).wrapping_mul(0xcb1ab31f);
+
+        hash ^= hash >> 16;
+        hash = hash.wrapping_mul(0x7feb352d);
+        hash ^= hash >> 15;
+        hash = hash.wrapping_mul(0x846ca68b);
+        hash ^= hash >> 16;
+
+        (hash >> 8) as f32 / 16777215.0 * 2.0 - 1.0
+    }
+
+    fn value_noise(pos: Vector3<f32>, seed: u32) -> f32 {
+        let cell = pos.map(|x| x.floor());
+        let t = (pos - cell).map(|t| t * t * t * (t * (t * 6.0 - 15.0) + 10.0));
+        let cell = cell.map(|x| x as i32);
+
+        let corner = |x: i32, y: i32, z: i32| Self::hash(cell + Vector3::new(x, y, z), seed);
+        let lerp = |a: f32, b: f32, t: f32| a + (b - a) * t;
+
+        lerp(
+            lerp(
+                lerp(corner(0, 0, 0), corner(1, 0, 0), t.x),
+                lerp(corner(0, 1, 0), corner(1, 1, 0), t.x),
+                t.y
+            ),
+            lerp(
+                lerp(corner(0, 0, 1), corner(1, 0, 1), t.x),
+                lerp(corner(0, 1, 1), corner(1, 1, 1), t.x),
+                t.y
+            ),
+            t.z
+        )
+    }
+}
+
+impl HeightFunction for Noise {
+    fn description(&self) -> String {
+        format!("Noise (seed {})", self.seed)
+    }
+
+    fn evaluate_wgsl(&self, name: &str) -> String {
+        format!("\
+            fn {name}(pos: vec3<f32>) -> f32 {{\
+      \n    var height = 0.0;\
+      \n    var amplitude = {amplitude:?};\
+      \n    var frequency = {frequency:?};\
+      \n    \
+      \n    for (var octave = 0u; octave < {octaves}u; octave += 1u) {{\
+      \n        height += amplitude * {name}_value_noise(frequency * pos, {seed}u + octave);\
+      \n        amplitude *= 0.5;\
+      \n        frequency *= 2.0;\
+      \n    }}\
+      \n    \
+      \n    return height;\
+      \n}}\
+      \n\
+      \nfn {name}_hash(cell: vec3<i32>, seed: u32) -> f32 {{\
+      \n    let c = bitcast<vec3<u32>>(cell);\
+      \n    var hash = seed ^ (c.x * 0x8da6b343u) ^ (c.y * 0xd8163841u) ^ (c.z * 0xcb1ab31fu);\
+      \n    \
+      \n    hash ^= hash >> 16u;\
+      \n    hash *= 0x7feb352du;\
+      \n    hash ^= hash >> 15u;\
+      \n    hash *= 0x846ca68bu;\
+      \n    hash ^= hash >> 16u;\
+      \n    \
+      \n    return f32(hash >> 8u) / 16777215.0 * 2.0 - 1.0;\
+      \n}}\
+      \n\
+      \nfn {name}_value_noise(pos: vec3<f32>, seed: u32) -> f32 {{\
+      \n    let cell_pos = floor(pos);\
+      \n    let f = pos - cell_pos;\
+      \n    let t = f * f * f * (f * (f * 6.0 - 15.0) + 10.0);\
+      \n    let cell = vec3<i32>(cell_pos);\
+      \n    \
+      \n    return mix(\
+      \n        mix(\
+      \n            mix({name}_hash(cell, seed), {name}_hash(cell + vec3(1, 0, 0), seed), t.x),\
+      \n            mix({name}_hash(cell + vec3(0, 1, 0), seed), {name}_hash(cell + vec3(1, 1, 0), seed), t.x),\
+      \n            t.y\
+      \n        ),\
+      \n        mix(\
+      \n            mix({name}_hash(cell + vec3(0, 0, 1), seed), {name}_hash(cell + vec3(1, 0, 1), seed), t.x),\
+      \n            mix({name}_hash(cell + vec3(0, 1, 1), seed), {name}_hash(cell + vec3(1, 1, 1), seed), t.x),\
+      \n            t.y\
+      \n        ),\
+      \n        t.z\
+      \n    );\
+      \n}}\
+        ",
+            name = name,
+            amplitude = self.amplitude,
+            frequency = self.frequency,
+            octaves = self.octaves,
+            seed = self.seed
+        )
+    }
+
+    fn evaluate(&self, pos: Vector3<f32>) -> f32 {
+        let mut height = 0.0;
+        let mut amplitude = self.amplitude;
+        let mut frequency = self.frequency;
+
+        for octave in 0..self.octaves {
+            height += amplitude * Self::value_noise(frequency * pos, self.seed.wrapping_add(octave));
+            amplitude *= 0.5;
+            frequency *= 2.0;
+        }
+
+        height
+    }
+}
+
+/// The graph w = h(x, y, z) + offset, where the offset puts the origin on it.
+/// The closest point is found with Gauss-Newton steps on |(p, h(p)) - pos|^2 starting from p = pos.xyz.
+#[derive(Debug)]
+pub struct Heightfield<HEIGHT: HeightFunction> {
+    height: HEIGHT,
+    offset: f32,
+    iterations: u32
+}
+
+impl<F: Fn(Vector3<f32>) -> f32> Heightfield<FnHeight<F>> {
+    pub fn new(function: F, function_wgsl: &str) -> Self {
+        Self::from_height(FnHeight::new(function, function_wgsl))
+    }
+}
+
+impl<HEIGHT: HeightFunction> Heightfield<HEIGHT> {
+    pub fn from_height(height: HEIGHT) -> Self {
+        Self {
+            offset: -height.evaluate(Vector3::zeros()),
+            height,
+            iterations: PROJECTION_ITERATIONS
+        }
+    }
+
+    pub fn height(&self) -> &HEIGHT {
+        &self.height
+    }
+}
+
+impl<HEIGHT: HeightFunction> Manifold for Heightfield<HEIGHT> {
+    fn description(&self) -> String {
+        format!("Heightfield: {}", self.height.description())
+    }
+
+    fn project_onto_wgsl(&self) -> String {
+        format!("\
+            let target_pos = pos.xyz;\
+      \n    let target_height = pos.w - manifold_info.v1.w;\
+      \n    var new_pos = target_pos;\
+      \n    \
+      \n    for (var i: f32 = 0.0; i < manifold_info.r1; i += 1.0) {{\
+      \n        let gradient = height_gradient(new_pos);\
+      \n        let residual = new_pos - target_pos + (height_function(new_pos) - target_height) * gradient;\
+      \n        new_pos -= residual - gradient * dot(gradient, residual) / (1.0 + dot(gradient, gradient));\
+      \n    }}\
+      \n    \
+      \n    return vec4(new_pos, height_function(new_pos) + manifold_info.v1.w);\
+      \n}}\
+      \n\
+      \n{}\
+      \n\
+      \nfn height_gradient(pos: vec3<f32>) -> vec3<f32> {{\
+      \n    let h = manifold_info.r2;\
+      \n    \
+      \n    return vec3(\
+      \n        height_function(pos + vec3(h, 0.0, 0.0)) - height_function(pos - vec3(h, 0.0, 0.0)),\
+      \n        height_function(pos + vec3(0.0, h, 0.0)) - height_function(pos - vec3(0.0, h, 0.0)),\
+      \n        height_function(pos + vec3(0.0, 0.0, h)) - height_function(pos - vec3(0.0, 0.0, h))\
+      \n    ) / (2.0 * h);\
+        ", self.height.evaluate_wgsl("height_function"))
+    }
+
+    fn project_onto(&self, pos: Vector4<f32>) -> Vector4<f32> {
+        let target_pos = pos.xyz();
+        let target_height = pos.w - self.offset;
+        let mut new_pos = target_pos;
+
+        // The closest point has new_pos - target_pos + (h(new_pos) - target_height) * grad h = 0,
+        // each step solves the linearized version with (I + g g^T)^-1 = I - g g^T / (1 + |g|^2)
+        for _ in 0..self.iterations {
+            let gradient = self.height.gradient(new_pos);
+            let residual = new_pos - target_pos + (self.height.evaluate(new_pos) - target_height) * gradient;
+
+            if residual.magnitude() < 0.01 * EPSILON {
+                break;
+            }
+
+            new_pos -= residual - gradient * gradient.dot(&residual) / (1.0 + gradient.magnitude_squared());
+        }
+
+        new_pos.push(self.height.evaluate(new_pos) + self.offset)
+    }
+
+    fn is_on_curve(&self, pos: Vector4<f32>) -> bool {
+        (pos.w - self.offset - self.height.evaluate(pos.xyz())).abs() < EPSILON
+    }
+
+    fn get_bytes(&self) -> Vec<u8> {
+        bytemuck::cast_slice(&[
+            &[0.0, 0.0, 0.0, self.offset],
+            Vector4::zeros().as_slice(),
+            Vector4::zeros().as_slice(),
+            &[ self.iterations as f32, GRADIENT_DELTA, 0.0 ]
+        ].concat()[..]).to_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_noise_is_seeded() {
+        let pos = Vector3::new(1.3, -2.7, 0.4);
+
+        assert_eq!(Noise::new(7, 4, 2.0, 0.5).evaluate(pos), Noise::new(7, 4, 2.0, 0.5).evaluate(pos));
+        assert_ne!(Noise::new(7, 4, 2.0, 0.5).evaluate(pos), Noise::new(8, 4, 2.0, 0.5).evaluate(pos));
+        assert!(Noise::new(7, 4, 2.0, 0.5).evaluate(pos).abs() <= 4.0);
+    }
+
+    #[test]
+    fn test_heightfield_projection_is_closest() {
+        let heightfield = Heightfield::from_height(Noise::new(3, 3, 1.0, 0.3));
+
+        for pos in [Vector4::new(1.0, 2.0, -1.0, 0.5), Vector4::new(-3.0, 0.5, 2.0, -0.4), Vector4::new(0.0, 0.0, 0.0, 0.2)] {
+            let new_pos = heightfield.project_onto(pos);
+            let gradient = heightfield.height().gradient(new_pos.xyz());
+            let normal = Vector4::new(-gradient.x, -gradient.y, -gradient.z, 1.0).normalize();
+
+            // pos - new_pos has to be along the normal of the graph
+            assert!(heightfield.is_on_curve(new_pos));
+            assert!(((pos - new_pos) - normal * normal.dot(&(pos - new_pos))).magnitude() < 1e-3, "{:?} => {:?}", pos, new_pos);
+        }
+    }
+}
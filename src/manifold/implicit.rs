@@ -0,0 +1,212 @@
+use nalgebra::Vector4;
+use crate::manifold::{EPSILON, Manifold};
+use crate::manifold::expression::{Expr, ExprError, Params, Type};
+
+const GRADIENT_DELTA: f32 = 0.001;
+const NEWTON_ITERATIONS: u32 = 16;
+
+/// A function f(x, y, z, w) whose zero set is the manifold
+pub trait ScalarField: std::fmt::Debug {
+    fn description(&self) -> String {
+        String::from("Scalar field")
+    }
+
+    /// A full wgsl definition of `fn {name}(pos: vec4<f32>) -> f32`
+    fn evaluate_wgsl(&self, name: &str) -> String;
+
+    fn evaluate(&self, pos: Vector4<f32>) -> f32;
+
+    fn gradient(&self, pos: Vector4<f32>) -> Vector4<f32> {
+        let mut gradient = Vector4::zeros();
+
+        for i in 0..4 {
+            let mut delta = Vector4::zeros();
+            delta[i] = GRADIENT_DELTA;
+
+            gradient[i] = (self.evaluate(pos + delta) - self.evaluate(pos - delta)) / (2.0 * GRADIENT_DELTA);
+        }
+
+        gradient
+    }
+}
+
+/// A scalar field from a rust closure along with the body of the matching wgsl function
+pub struct FnField<F: Fn(Vector4<f32>) -> f32> {
+    function: F,
+    function_wgsl: String
+}
+
+impl<F: Fn(Vector4<f32>) -> f32> FnField<F> {
+    pub fn new(function: F, function_wgsl: &str) -> Self {
+        Self { function, function_wgsl: function_wgsl.to_string() }
+    }
+}
+
+impl<F: Fn(Vector4<f32>) -> f32> std::fmt::Debug for FnField<F> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FnField")
+            .field("function_wgsl", &self.function_wgsl)
+            .finish()
+    }
+}
+
+impl<F: Fn(Vector4<f32>) -> f32> ScalarField for FnField<F> {
+    fn evaluate_wgsl(&self, name: &str) -> String {
+        format!("\
+            fn {}(pos: vec4<f32>) -> f32 {{\
+      \n    {}\
+      \n}}\
+        ", name, self.function_wgsl)
+    }
+
+    fn evaluate(&self, pos: Vector4<f32>) -> f32 {
+        (self.function)(pos)
+    }
+}
+
+/// A scalar field from an expression, which can't use params since ImplicitManifold has its own uniforms
+#[derive(Debug, Clone)]
+pub struct ExprField {
+    expr: Expr
+}
+
+impl ExprField {
+    pub fn new(expr: Expr) -> Result<Self, ExprError> {
+        match expr.get_type(4)? {
+            Type::Scalar if !expr.uses_params() => Ok(Self { expr }),
+            Type::Scalar => Err(ExprError::InvalidVariableName(String::from("manifold_info"))),
+            found => Err(ExprError::UnexpectedType { expected: Type::Scalar, found })
+        }
+    }
+
+    pub fn expr(&self) -> &Expr {
+        &self.expr
+    }
+}
+
+impl ScalarField for ExprField {
+    fn description(&self) -> String {
+        String::from("Expression")
+    }
+
+    fn evaluate_wgsl(&self, name: &str) -> String {
+        format!("\
+            fn {}(pos: vec4<f32>) -> f32 {{\
+      \n    {}\
+      \n}}\
+        ", name, self.expr.to_wgsl(4).expect("Expression was type checked in ExprField::new"))
+    }
+
+    fn evaluate(&self, pos: Vector4<f32>) -> f32 {
+        self.expr.evaluate(pos.as_slice(), &Params::default())
+            .and_then(|value| value.as_scalar())
+            .expect("Expression was type checked in ExprField::new")
+    }
+}
+
+/// The hypersurface f(pos - center) = 0, points are projected onto it with Newton steps along the gradient
+#[derive(Debug)]
+pub struct ImplicitManifold<FIELD: ScalarField> {
+    field: FIELD,
+    center: Vector4<f32>,
+    iterations: u32,
+    tolerance: f32
+}
+
+impl<F: Fn(Vector4<f32>) -> f32> ImplicitManifold<FnField<F>> {
+    pub fn new(function: F, function_wgsl: &str, center: Vector4<f32>) -> Self {
+        Self::from_field(FnField::new(function, function_wgsl), center)
+    }
+}
+
+impl<FIELD: ScalarField> ImplicitManifold<FIELD> {
+    pub fn from_field(field: FIELD, center: Vector4<f32>) -> Self {
+        Self {
+            field,
+            center,
+            iterations: NEWTON_ITERATIONS,
+            tolerance: EPSILON
+        }
+    }
+
+    pub fn field(&self) -> &FIELD {
+        &self.field
+    }
+}
+
+impl<FIELD: ScalarField> Manifold for ImplicitManifold<FIELD> {
+    fn description(&self) -> String {
+        format!("Implicit: {}", self.field.description())
+    }
+
+    fn project_onto_wgsl(&self) -> String {
+        format!("\
+            var new_pos: vec4<f32> = pos - manifold_info.v1;\
+      \n    \
+      \n    for (var i: f32 = 0.0; i < manifold_info.r1; i += 1.0) {{\
+      \n        let gradient = implicit_gradient(new_pos);\
+      \n        new_pos -= implicit_function(new_pos) / max(dot(gradient, gradient), 1e-12) * gradient;\
+      \n    }}\
+      \n    \
+      \n    return new_pos + manifold_info.v1;\
+      \n}}\
+      \n\
+      \n{}\
+      \n\
+      \nfn implicit_gradient(pos: vec4<f32>) -> vec4<f32> {{\
+      \n    let h = manifold_info.r2;\
+      \n    \
+      \n    return vec4(\
+      \n        implicit_function(pos + vec4(h, 0.0, 0.0, 0.0)) - implicit_function(pos - vec4(h, 0.0, 0.0, 0.0)),\
+      \n        implicit_function(pos + vec4(0.0, h, 0.0, 0.0)) - implicit_function(pos - vec4(0.0, h, 0.0, 0.0)),\
+      \n        implicit_function(pos + vec4(0.0, 0.0, h, 0.0)) - implicit_function(pos - vec4(0.0, 0.0, h, 0.0)),\
+      \n        implicit_function(pos + vec4(0.0, 0.0, 0.0, h)) - implicit_function(pos - vec4(0.0, 0.0, 0.0, h))\
+      \n    ) / (2.0 * h);\
+        ", self.field.evaluate_wgsl("implicit_function"))
+    }
+
+    fn project_onto(&self, pos: Vector4<f32>) -> Vector4<f32> {
+        let mut new_pos = pos - self.center;
+
+        for _ in 0..self.iterations {
+            let value = self.field.evaluate(new_pos);
+
+            if value.abs() < 0.01 * self.tolerance {
+                break;
+            }
+
+            let gradient = self.field.gradient(new_pos);
+
+            if gradient.magnitude_squared() == 0.0 {
+                new_pos.x += 0.0001;
+                continue;
+            }
+
+            new_pos -= value / gradient.magnitude_squared() * gradient;
+        }
+
+        new_pos + self.center
+    }
+
+    fn is_on_curve(&self, pos: Vector4<f32>) -> bool {
+        self.field.evaluate(pos - self.center).abs() < self.tolerance
+    }
+
+    /// The normalized gradient, which points towards where the field is positive
+    fn normal_at(&self, pos: Vector4<f32>) -> Vector4<f32> {
+        self.field.gradient(self.project_onto(pos) - self.center).normalize()
+    }
+
+    fn normal_at_wgsl(&self) -> String {
+        String::from("return normalize(implicit_gradient(project_onto_curve(pos) - manifold_info.v1));")
+    }
+
+    fn get_bytes(&self) -> Vec<u8> {
+        bytemuck::cast_slice(&[
+            self.center.as_slice(),
+            Vector4::zeros().as_slice(),
+            Vector4::zeros().as_slice(),
+            &[ self.iterations as f32, GRADIENT_DELTA, self.tolerance ]
+        ].concat()[..]).to_owned()
+    }
+}
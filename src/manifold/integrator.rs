@@ -0,0 +1,275 @@
+use nalgebra::{Vector3, Vector4};
+use winit::event::VirtualKeyCode;
+use crate::manifold::{Manifold, Point};
+
+/// The distance used for finite differences of the projection
+const DIFFERENCE_STEP: f32 = 0.05;
+
+/// Most substeps taken to cover one step, if they run out the step is cut short
+const MAX_SUBSTEPS: usize = 16;
+
+/// Substeps are never halved below this fraction of the step
+const MIN_SUBSTEP_FRACTION: f32 = 1.0 / 64.0;
+
+/// Steps along geodesics of the inner manifold by integrating x'' = a(x, x') with RK4 instead of stepping off the
+/// surface and projecting back. The acceleration is the second derivative of the projection along x', which is the
+/// normal curvature the geodesic needs to stay on the surface, and the step size adapts by comparing a full step against
+/// two half steps. Intrinsic manifolds already step exactly so there's no point wrapping them.
+#[derive(Debug)]
+pub struct Rk4<M: Manifold> {
+    manifold: M,
+    tolerance: f32
+}
+
+impl<M: Manifold> Rk4<M> {
+    /// Substeps are halved until their estimated error is below tolerance
+    pub fn new(manifold: M, tolerance: f32) -> Self {
+        assert!(tolerance > 0.0, "tolerance must be positive");
+
+        Self { manifold, tolerance }
+    }
+
+    pub fn manifold(&self) -> &M {
+        &self.manifold
+    }
+
+    fn acceleration(&self, pos: Vector4<f32>, vel: Vector4<f32>) -> Vector4<f32> {
+        let h = DIFFERENCE_STEP;
+
+        (
+            self.manifold.project_onto(pos + h * vel) +
+            self.manifold.project_onto(pos - h * vel) -
+            2.0 * self.manifold.project_onto(pos)
+        ) / h.powi(2)
+    }
+
+    fn rk4(&self, pos: Vector4<f32>, vel: Vector4<f32>, h: f32) -> (Vector4<f32>, Vector4<f32>) {
+        let (k1x, k1v) = (vel, self.acceleration(pos, vel));
+        let k2x = vel + 0.5 * h * k1v;
+        let k2v = self.acceleration(pos + 0.5 * h * k1x, k2x);
+        let k3x = vel + 0.5 * h * k2v;
+        let k3v = self.acceleration(pos + 0.5 * h * k2x, k3x);
+        let k4x = vel + h * k3v;
+        let k4v = self.acceleration(pos + h * k3x, k4x);
+
+        (
+            pos + h / 6.0 * (k1x + 2.0 * k2x + 2.0 * k3x + k4x),
+            vel + h / 6.0 * (k1v + 2.0 * k2v + 2.0 * k3v + k4v)
+        )
+    }
+
+    /// A step of size h taken as two half steps, along with its error estimated from a single full step
+    pub fn rk4_step(&self, pos: Vector4<f32>, vel: Vector4<f32>, h: f32) -> (Vector4<f32>, Vector4<f32>, f32) {
+        let (full_pos, _) = self.rk4(pos, vel, h);
+        let (half_pos, half_vel) = self.rk4(pos, vel, 0.5 * h);
+        let (new_pos, new_vel) = self.rk4(half_pos, half_vel, 0.5 * h);
+
+        (new_pos, new_vel, (new_pos - full_pos).magnitude() / 15.0)
+    }
+}
+
+impl<M: Manifold> Manifold for Rk4<M> {
+    fn description(&self) -> String {
+        format!("{} (RK4)", self.manifold.description())
+    }
+
+    fn project_onto_wgsl(&self) -> String {
+        self.manifold.project_onto_wgsl()
+    }
+
+    fn project_onto(&self, pos: Vector4<f32>) -> Vector4<f32> {
+        self.manifold.project_onto(pos)
+    }
+
+    fn step_along_curve(&self, point: Point, delta: f32) -> Point {
+        let (mut pos, mut vel) = (point.pos, point.ray.normalize());
+        let mut remaining = delta;
+        let mut h = delta;
+
+        for _ in 0..MAX_SUBSTEPS {
+            let substep = h.min(remaining);
+            let (new_pos, new_vel, error) = self.rk4_step(pos, vel, substep);
+
+            if error <= self.tolerance || substep <= MIN_SUBSTEP_FRACTION * delta {
+                (pos, vel) = (new_pos, new_vel);
+                remaining -= substep;
+
+                if remaining <= 0.0 {
+                    break;
+                } else if error < self.tolerance / 32.0 {
+                    h *= 2.0;
+                }
+            } else {
+                h *= 0.5;
+            }
+        }
+
+        // Removes the drift off the surface and out of the tangent space
+        let h = DIFFERENCE_STEP;
+        let new_pos = self.manifold.project_onto(pos);
+        let tangent = self.manifold.project_onto(new_pos + h * vel) - self.manifold.project_onto(new_pos - h * vel);
+
+        Point {
+            pos: new_pos,
+            ray: tangent.normalize()
+        }
+    }
+
+    fn step_along_curve_wgsl(&self) -> String {
+        format!("\
+                var pos = point.pos;\
+          \n    var vel = normalize(point.ray);\
+          \n    var remaining = delta;\
+          \n    var h = delta;\
+          \n    \
+          \n    for (var i = 0; i < {0}; i++) {{\
+          \n        let substep = min(h, remaining);\
+          \n        let full = rk4(pos, vel, substep);\
+          \n        let half = rk4(pos, vel, 0.5 * substep);\
+          \n        let halves = rk4(half.pos, half.ray, 0.5 * substep);\
+          \n        let error = length(halves.pos - full.pos) / 15.0;\
+          \n        \
+          \n        if (error <= {1:?} || substep <= {2:?} * delta) {{\
+          \n            pos = halves.pos;\
+          \n            vel = halves.ray;\
+          \n            remaining -= substep;\
+          \n            \
+          \n            if (remaining <= 0.0) {{\
+          \n                break;\
+          \n            }} else if (error < {1:?} / 32.0) {{\
+          \n                h *= 2.0;\
+          \n            }}\
+          \n        }} else {{\
+          \n            h *= 0.5;\
+          \n        }}\
+          \n    }}\
+          \n    \
+          \n    let new_pos = project_onto_curve(pos);\
+          \n    let tangent = project_onto_curve(new_pos + {3:?} * vel) - project_onto_curve(new_pos - {3:?} * vel);\
+          \n    \
+          \n    return Point(new_pos, normalize(tangent));\
+          \n}}\
+          \n\
+          \nfn rk4_acceleration(pos: vec4<f32>, vel: vec4<f32>) -> vec4<f32> {{\
+          \n    return (\
+          \n        project_onto_curve(pos + {3:?} * vel) +\
+          \n        project_onto_curve(pos - {3:?} * vel) -\
+          \n        2.0 * project_onto_curve(pos)\
+          \n    ) / {4:?};\
+          \n}}\
+          \n\
+          \n// The new position and velocity after a step of size h\
+          \nfn rk4(pos: vec4<f32>, vel: vec4<f32>, h: f32) -> Point {{\
+          \n    let k1x = vel;\
+          \n    let k1v = rk4_acceleration(pos, vel);\
+          \n    let k2x = vel + 0.5 * h * k1v;\
+          \n    let k2v = rk4_acceleration(pos + 0.5 * h * k1x, k2x);\
+          \n    let k3x = vel + 0.5 * h * k2v;\
+          \n    let k3v = rk4_acceleration(pos + 0.5 * h * k2x, k3x);\
+          \n    let k4x = vel + h * k3v;\
+          \n    let k4v = rk4_acceleration(pos + h * k3x, k4x);\
+          \n    \
+          \n    return Point(\
+          \n        pos + h / 6.0 * (k1x + 2.0 * k2x + 2.0 * k3x + k4x),\
+          \n        vel + h / 6.0 * (k1v + 2.0 * k2v + 2.0 * k3v + k4v)\
+          \n    );\
+            ",
+            MAX_SUBSTEPS,
+            self.tolerance,
+            MIN_SUBSTEP_FRACTION,
+            DIFFERENCE_STEP,
+            DIFFERENCE_STEP.powi(2)
+        )
+    }
+
+    fn normal_at(&self, pos: Vector4<f32>) -> Vector4<f32> {
+        self.manifold.normal_at(pos)
+    }
+
+    fn normal_at_wgsl(&self) -> String {
+        self.manifold.normal_at_wgsl()
+    }
+
+    fn inner_product(&self, pos: Vector4<f32>, a: Vector4<f32>, b: Vector4<f32>) -> f32 {
+        self.manifold.inner_product(pos, a, b)
+    }
+
+    fn tangent_basis(&self, pos: Vector4<f32>) -> [Vector4<f32>; 3] {
+        self.manifold.tangent_basis(pos)
+    }
+
+    fn parallel_transport(&self, vector: Vector4<f32>, pos: Vector4<f32>) -> Vector4<f32> {
+        self.manifold.parallel_transport(vector, pos)
+    }
+
+    fn intrinsic_coords(&self, pos: Vector4<f32>) -> Vector3<f32> {
+        self.manifold.intrinsic_coords(pos)
+    }
+
+    fn intrinsic_coords_wgsl(&self) -> String {
+        self.manifold.intrinsic_coords_wgsl()
+    }
+
+    fn point_at_intrinsic_coords(&self, coords: Vector3<f32>) -> Vector4<f32> {
+        self.manifold.point_at_intrinsic_coords(coords)
+    }
+
+    fn point_at_intrinsic_coords_wgsl(&self) -> String {
+        self.manifold.point_at_intrinsic_coords_wgsl()
+    }
+
+    fn in_bounds(&self, pos: Vector4<f32>) -> bool {
+        self.manifold.in_bounds(pos)
+    }
+
+    fn in_bounds_wgsl(&self) -> String {
+        self.manifold.in_bounds_wgsl()
+    }
+
+    fn identify(&self, point: Point, frame: &mut [Vector4<f32>]) -> Point {
+        self.manifold.identify(point, frame)
+    }
+
+    fn identify_wgsl(&self) -> String {
+        self.manifold.identify_wgsl()
+    }
+
+    fn is_on_curve(&self, pos: Vector4<f32>) -> bool {
+        self.manifold.is_on_curve(pos)
+    }
+
+    fn get_bytes(&self) -> Vec<u8> {
+        self.manifold.get_bytes()
+    }
+
+    fn change_on_keybinds(&mut self, key_code: &VirtualKeyCode) {
+        self.manifold.change_on_keybinds(key_code);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::manifold::shapes4D::Hypersphere;
+    use super::*;
+
+    #[test]
+    fn test_rk4_follows_great_circles() {
+        let sphere = Rk4::new(Hypersphere::new(2.0), 1e-5);
+        let start = Vector4::<f32>::zeros();
+        let mut point = Point { pos: start, ray: Vector4::new(1.0, 0.5, 0.0, 0.0).normalize() };
+
+        for _ in 0..20 {
+            point = sphere.advance_point(point, 0.25);
+        }
+
+        // The distance along the great circle is radius * angle, the projected steps would fall short by about 0.03
+        let center = 2.0 * Vector4::<f32>::w();
+        let angle = ((start - center).dot(&(point.pos - center)) / 4.0).acos();
+
+        assert!((2.0 * angle - 5.0).abs() < 1e-3);
+
+        let (_, _, error) = sphere.rk4_step(start, Vector4::x(), 0.25);
+
+        assert!(error < 1e-5);
+    }
+}
@@ -0,0 +1,357 @@
+use nalgebra::{Vector3, Vector4};
+use crate::manifold::{EPSILON, Manifold, Point};
+use crate::manifold::shapes4D::Hypersphere;
+use crate::manifold::expression::Expr;
+
+/// The inner product of Minkowski space, dx² + dy² + dz² - dw²
+fn minkowski_dot(a: Vector4<f32>, b: Vector4<f32>) -> f32 {
+    a.xyz().dot(&b.xyz()) - a.w * b.w
+}
+
+/// Follows the great circle q cos(s) + v sin(s) of the sphere, where v is the ray made tangent and unit length
+pub(crate) fn great_circle(center: Vector4<f32>, radius: f32, point: &Point, t: f32) -> Point {
+    let q = point.pos - center;
+    let tangent = point.ray - q.dot(&point.ray) / radius.powi(2) * q;
+    let tangent = radius * tangent / tangent.magnitude().max(1e-6);
+    let s = t / radius;
+
+    Point {
+        pos: q * s.cos() + tangent * s.sin() + center,
+        ray: (tangent * s.cos() - q * s.sin()) / radius
+    }
+}
+
+/// great_circle for spheres with their center in v1 and radius in r1
+pub(crate) const GREAT_CIRCLE_WGSL: &str = "\
+    let q = point.pos - manifold_info.v1;\
+\n    let r = manifold_info.r1;\
+\n    let tangent = point.ray - dot(q, point.ray) / (r * r) * q;\
+\n    let unit_tangent = r * tangent / max(length(tangent), 1e-6);\
+\n    let s = t / r;\
+\n    \
+\n    return Point(\
+\n        q * cos(s) + unit_tangent * sin(s) + manifold_info.v1,\
+\n        (unit_tangent * cos(s) - q * sin(s)) / r\
+\n    );\
+";
+
+/// Angles (chi, theta, phi) around the sphere, chi from the x axis, theta from the y axis and phi in the zw plane.
+/// The poles are on the x axis, away from the origin where the camera starts
+pub(crate) fn hyperspherical_angles(center: Vector4<f32>, pos: Vector4<f32>) -> Vector3<f32> {
+    let q = pos - center;
+
+    Vector3::new(
+        (q.x / q.magnitude().max(1e-6)).clamp(-1.0, 1.0).acos(),
+        (q.y / Vector3::new(q.y, q.z, q.w).magnitude().max(1e-6)).clamp(-1.0, 1.0).acos(),
+        q.w.atan2(q.z)
+    )
+}
+
+/// The point on the sphere with the given hyperspherical_angles
+pub(crate) fn point_at_hyperspherical_angles(center: Vector4<f32>, radius: f32, angles: Vector3<f32>) -> Vector4<f32> {
+    let (chi, theta, phi) = (angles.x, angles.y, angles.z);
+
+    radius * Vector4::new(
+        chi.cos(),
+        chi.sin() * theta.cos(),
+        chi.sin() * theta.sin() * phi.cos(),
+        chi.sin() * theta.sin() * phi.sin()
+    ) + center
+}
+
+/// hyperspherical_angles for spheres with their center in v1
+pub(crate) const HYPERSPHERICAL_ANGLES_WGSL: &str = "\
+    let q = pos - manifold_info.v1;\
+\n    \
+\n    return vec3(\
+\n        acos(clamp(q.x / max(length(q), 1e-6), -1.0, 1.0)),\
+\n        acos(clamp(q.y / max(length(q.yzw), 1e-6), -1.0, 1.0)),\
+\n        atan2(q.w, q.z)\
+\n    );\
+";
+
+/// point_at_hyperspherical_angles for spheres with their center in v1 and radius in r1
+pub(crate) const POINT_AT_HYPERSPHERICAL_ANGLES_WGSL: &str = "\
+    let s = sin(coords.x);\
+\n    \
+\n    return manifold_info.r1 * vec4(\
+\n        cos(coords.x),\
+\n        s * cos(coords.y),\
+\n        s * sin(coords.y) * cos(coords.z),\
+\n        s * sin(coords.y) * sin(coords.z)\
+\n    ) + manifold_info.v1;\
+";
+
+/// Hyperbolic 3-space with curvature -1 / radius² in the hyperboloid model, the sheet (w + radius)² - x² - y² - z² = radius²
+/// of Minkowski space. It is defined by its metric rather than its embedding, so steps follow its geodesics exactly
+/// and project_onto is only used to place the camera and rays, normalizing points in the Minkowski metric
+#[derive(Debug)]
+pub struct Hyperbolic {
+    radius: f32,
+    center: Vector4<f32>
+}
+
+impl Hyperbolic {
+    pub fn new(radius: f32) -> Self {
+        Self {
+            radius, center: -radius * Vector4::w()
+        }
+    }
+}
+
+impl Manifold for Hyperbolic {
+    fn description(&self) -> String {
+        String::from("Hyperbolic space")
+    }
+
+    fn get_bytes(&self) -> Vec<u8> {
+        bytemuck::cast_slice(&[
+            self.center.as_slice(),
+            Vector4::zeros().as_slice(),
+            Vector4::zeros().as_slice(),
+            &[ self.radius, 0.0, 0.0 ]
+        ].concat()[..]).to_owned()
+    }
+
+    fn project_onto_wgsl(&self) -> String {
+        String::from("\
+            let q = pos - manifold_info.v1;\
+      \n    let r = manifold_info.r1;\
+      \n    \
+      \n    if (q.w > length(q.xyz)) {\
+      \n        return r * q / sqrt(q.w * q.w - dot(q.xyz, q.xyz)) + manifold_info.v1;\
+      \n    }\
+      \n    \
+      \n    return vec4(q.xyz, sqrt(r * r + dot(q.xyz, q.xyz))) + manifold_info.v1;\
+        ")
+    }
+
+    /// Scales future pointing vectors onto the hyperboloid and lifts everything else straight up in w
+    fn project_onto(&self, pos: Vector4<f32>) -> Vector4<f32> {
+        let q = pos - self.center;
+
+        if q.w > q.xyz().magnitude() {
+            self.radius * q / (-minkowski_dot(q, q)).sqrt() + self.center
+        } else {
+            Vector4::new(q.x, q.y, q.z, (self.radius.powi(2) + q.xyz().magnitude_squared()).sqrt()) + self.center
+        }
+    }
+
+    /// The euclidean normal, the gradient of x² + y² + z² - w² which points away from the center
+    fn normal_at(&self, pos: Vector4<f32>) -> Vector4<f32> {
+        let q = self.project_onto(pos) - self.center;
+
+        Vector4::new(q.x, q.y, q.z, -q.w).normalize()
+    }
+
+    fn normal_at_wgsl(&self) -> String {
+        String::from("\
+            let q = project_onto_curve(pos) - manifold_info.v1;\
+      \n    \
+      \n    return normalize(vec4(q.xyz, -q.w));\
+        ")
+    }
+
+    fn inner_product(&self, _pos: Vector4<f32>, a: Vector4<f32>, b: Vector4<f32>) -> f32 {
+        minkowski_dot(a, b)
+    }
+
+    /// Gram-Schmidt of the x, y and z axes in the Minkowski metric, after removing their parts along q
+    fn tangent_basis(&self, pos: Vector4<f32>) -> [Vector4<f32>; 3] {
+        let q = self.project_onto(pos) - self.center;
+        let mut basis = [Vector4::zeros(); 3];
+
+        for i in 0..3 {
+            let mut axis = Vector4::ith(i, 1.0) + q[i] / self.radius.powi(2) * q;
+
+            for previous in basis[..i].iter() {
+                axis -= minkowski_dot(axis, *previous) * previous;
+            }
+
+            basis[i] = axis / minkowski_dot(axis, axis).sqrt();
+        }
+
+        basis
+    }
+
+    /// Removes the Minkowski part of vector along q and rescales it to its Minkowski length, tangent vectors
+    /// being spacelike
+    fn parallel_transport(&self, vector: Vector4<f32>, pos: Vector4<f32>) -> Vector4<f32> {
+        let q = self.project_onto(pos) - self.center;
+        let tangent = vector + minkowski_dot(q, vector) / self.radius.powi(2) * q;
+        let length = minkowski_dot(vector, vector).max(0.0).sqrt();
+
+        tangent * length / minkowski_dot(tangent, tangent).max(1e-12).sqrt()
+    }
+
+    /// Follows the geodesic q cosh(s) + v sinh(s), where v is the ray made tangent and unit length in the Minkowski metric
+    fn geodesic(&self, point: &Point, t: f32) -> Option<Point> {
+        let q = point.pos - self.center;
+        let tangent = point.ray + minkowski_dot(q, point.ray) / self.radius.powi(2) * q;
+        let tangent = self.radius * tangent / minkowski_dot(tangent, tangent).max(1e-12).sqrt();
+        let s = t / self.radius;
+
+        Some(Point {
+            pos: q * s.cosh() + tangent * s.sinh() + self.center,
+            ray: (q * s.sinh() + tangent * s.cosh()) / self.radius
+        })
+    }
+
+    fn geodesic_wgsl(&self) -> Option<String> {
+        Some(String::from("\
+            let q = point.pos - manifold_info.v1;\
+      \n    let r = manifold_info.r1;\
+      \n    let tangent = point.ray + (dot(q.xyz, point.ray.xyz) - q.w * point.ray.w) / (r * r) * q;\
+      \n    let unit_tangent = r * tangent / sqrt(max(dot(tangent.xyz, tangent.xyz) - tangent.w * tangent.w, 1e-12));\
+      \n    let s = t / r;\
+      \n    \
+      \n    return Point(\
+      \n        q * cosh(s) + unit_tangent * sinh(s) + manifold_info.v1,\
+      \n        (q * sinh(s) + unit_tangent * cosh(s)) / r\
+      \n    );\
+        "))
+    }
+
+    /// x, y and z relative to the bottom of the hyperboloid, which it is a graph over
+    fn intrinsic_coords(&self, pos: Vector4<f32>) -> Vector3<f32> {
+        (pos - self.center).xyz()
+    }
+
+    fn intrinsic_coords_wgsl(&self) -> String {
+        String::from("return (pos - manifold_info.v1).xyz;")
+    }
+
+    fn point_at_intrinsic_coords(&self, coords: Vector3<f32>) -> Vector4<f32> {
+        Vector4::new(coords.x, coords.y, coords.z, (self.radius.powi(2) + coords.magnitude_squared()).sqrt()) + self.center
+    }
+
+    fn point_at_intrinsic_coords_wgsl(&self) -> String {
+        String::from("return vec4(coords, sqrt(manifold_info.r1 * manifold_info.r1 + dot(coords, coords))) + manifold_info.v1;")
+    }
+
+    fn is_on_curve(&self, pos: Vector4<f32>) -> bool {
+        let q = pos - self.center;
+
+        (minkowski_dot(q, q) + self.radius.powi(2)).abs() < EPSILON && q.w > 0.0
+    }
+}
+
+/// The 3-sphere with curvature 1 / radius², the same space as the Hypersphere so everything is forwarded to one
+#[derive(Debug)]
+pub struct ThreeSphere {
+    sphere: Hypersphere
+}
+
+impl ThreeSphere {
+    pub fn new(radius: f32) -> Self {
+        Self { sphere: Hypersphere::new(radius) }
+    }
+}
+
+impl Manifold for ThreeSphere {
+    fn description(&self) -> String {
+        String::from("3-Sphere")
+    }
+
+    fn get_bytes(&self) -> Vec<u8> {
+        self.sphere.get_bytes()
+    }
+
+    fn project_onto_wgsl(&self) -> String {
+        self.sphere.project_onto_wgsl()
+    }
+
+    fn projection_expr(&self) -> Option<Expr> {
+        self.sphere.projection_expr()
+    }
+
+    fn project_onto(&self, pos: Vector4<f32>) -> Vector4<f32> {
+        self.sphere.project_onto(pos)
+    }
+
+    fn normal_at(&self, pos: Vector4<f32>) -> Vector4<f32> {
+        self.sphere.normal_at(pos)
+    }
+
+    fn normal_at_wgsl(&self) -> String {
+        self.sphere.normal_at_wgsl()
+    }
+
+    fn geodesic(&self, point: &Point, t: f32) -> Option<Point> {
+        self.sphere.geodesic(point, t)
+    }
+
+    fn geodesic_wgsl(&self) -> Option<String> {
+        self.sphere.geodesic_wgsl()
+    }
+
+    fn intrinsic_coords(&self, pos: Vector4<f32>) -> Vector3<f32> {
+        self.sphere.intrinsic_coords(pos)
+    }
+
+    fn intrinsic_coords_wgsl(&self) -> String {
+        self.sphere.intrinsic_coords_wgsl()
+    }
+
+    fn point_at_intrinsic_coords(&self, coords: Vector3<f32>) -> Vector4<f32> {
+        self.sphere.point_at_intrinsic_coords(coords)
+    }
+
+    fn point_at_intrinsic_coords_wgsl(&self) -> String {
+        self.sphere.point_at_intrinsic_coords_wgsl()
+    }
+
+    fn is_on_curve(&self, pos: Vector4<f32>) -> bool {
+        self.sphere.is_on_curve(pos)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_steps_follow_geodesics() {
+        let start = Point { pos: Vector4::zeros(), ray: Vector4::new(1.0, 0.5, 0.0, 0.0).normalize() };
+        let hyperbolic = Hyperbolic::new(2.0);
+        let mut point = Point { pos: start.pos, ray: start.ray };
+
+        for _ in 0..100 {
+            point = hyperbolic.advance_point(point, 0.05);
+        }
+
+        // The hyperbolic distance from the start is radius * acosh(-<p, q> / radius²)
+        let (p, q) = (start.pos - hyperbolic.center, point.pos - hyperbolic.center);
+
+        assert!(hyperbolic.is_on_curve(point.pos));
+        assert!((2.0 * (-minkowski_dot(p, q) / 4.0).acosh() - 5.0).abs() < 1e-2);
+
+        let sphere = ThreeSphere::new(2.0);
+        let mut point = Point { pos: start.pos, ray: start.ray };
+
+        for _ in 0..100 {
+            point = sphere.advance_point(point, 0.05);
+        }
+
+        let center = Vector4::new(0.0, 0.0, 0.0, 2.0);
+        let (p, q) = (start.pos - center, point.pos - center);
+
+        assert!(sphere.is_on_curve(point.pos));
+        assert!((2.0 * (p.dot(&q) / 4.0).acos() - 5.0).abs() < 1e-2);
+    }
+
+    #[test]
+    fn test_hyperbolic_exp_map_distance() {
+        let hyperbolic = Hyperbolic::new(2.0);
+        let from = hyperbolic.project_onto(Vector4::new(0.5, -0.3, 0.2, 0.0));
+        let basis = hyperbolic.tangent_basis(from);
+        let tangent = 1.5 * basis[0] - 2.0 * basis[2];
+        let to = hyperbolic.exp_map(from, tangent).pos;
+
+        // Lands at hyperbolic distance radius * acosh(-<p, q> / radius²) equal to the Minkowski length of the tangent
+        let (p, q) = (from - hyperbolic.center, to - hyperbolic.center);
+
+        assert!(hyperbolic.is_on_curve(to));
+        assert!((2.0 * (-minkowski_dot(p, q) / 4.0).acosh() - 2.5).abs() < 1e-2);
+    }
+}
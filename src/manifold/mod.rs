@@ -1,11 +1,109 @@
 pub mod shapes4D;
 pub mod shapes3D;
+pub mod shapes2D;
+pub mod shapes1D;
+pub mod product;
+pub mod transformed;
+mod closest_point;
+pub mod implicit;
+pub mod heightfield;
+pub mod tube;
+pub mod csg;
+pub mod quotient;
+pub mod intrinsic;
+pub mod integrator;
+pub mod curvature;
+pub mod shortest_path;
+pub mod sampling;
+pub mod boundary;
+pub mod expression;
+pub mod parser;
 
-use nalgebra::Vector4;
+use nalgebra::{Matrix3, Matrix4x3, Vector3, Vector4};
 use winit::event::VirtualKeyCode;
+use crate::manifold::expression::Expr;
 
 const EPSILON: f32 = 0.01;
 
+/// Number of vec4s in the data array at the end of ManifoldInfo in the shader
+pub const MANIFOLD_DATA_LEN: usize = 64;
+
+/// Size in bytes of ManifoldInfo, the data array starts after v1, v2, v3, r1, r2, r3 and padding
+pub const MANIFOLD_INFO_SIZE: usize = 64 + 16 * MANIFOLD_DATA_LEN;
+
+/// A wgsl literal for a constant vector, for manifolds that bake values into their wgsl
+pub(crate) fn vec4_wgsl(vector: Vector4<f32>) -> String {
+    format!("vec4({:?}, {:?}, {:?}, {:?})", vector.x, vector.y, vector.z, vector.w)
+}
+
+/// The distance used to differentiate project_onto for normals
+const NORMAL_DELTA: f32 = 0.01;
+
+/// The longest step exp_map takes along manifolds without closed form geodesics
+const EXP_MAP_STEP: f32 = 0.05;
+
+/// How close exp_map of the answer has to land to the target for log_map to stop
+const LOG_MAP_TOLERANCE: f32 = 1e-4;
+
+/// Most Newton iterations log_map takes before giving up
+const LOG_MAP_ITERATIONS: usize = 32;
+
+/// The default body of normal_at in the shader, the same as Manifold::normal_at
+const NORMAL_AT_WGSL: &str = "\
+    let on_curve = project_onto_curve(pos);\
+\n    let h = 0.01;\
+\n    var columns = array<vec4<f32>, 4>(\
+\n        project_onto_curve(on_curve + vec4(h, 0.0, 0.0, 0.0)) - project_onto_curve(on_curve - vec4(h, 0.0, 0.0, 0.0)),\
+\n        project_onto_curve(on_curve + vec4(0.0, h, 0.0, 0.0)) - project_onto_curve(on_curve - vec4(0.0, h, 0.0, 0.0)),\
+\n        project_onto_curve(on_curve + vec4(0.0, 0.0, h, 0.0)) - project_onto_curve(on_curve - vec4(0.0, 0.0, h, 0.0)),\
+\n        project_onto_curve(on_curve + vec4(0.0, 0.0, 0.0, h)) - project_onto_curve(on_curve - vec4(0.0, 0.0, 0.0, h))\
+\n    );\
+\n    var shortest = 0;\
+\n    \
+\n    for (var i = 1; i < 4; i++) {\
+\n        if (dot(columns[i], columns[i]) < dot(columns[shortest], columns[shortest])) {\
+\n            shortest = i;\
+\n        }\
+\n    }\
+\n    \
+\n    columns[shortest] = columns[3];\
+\n    \
+\n    let normal = normalize(cross4(columns[0], columns[1], columns[2]));\
+\n    \
+\n    return select(normal, -normal, dot(normal, pos - on_curve) < 0.0);\
+";
+
+/// The default body of step_along_curve in the shader
+const STEP_ALONG_CURVE_WGSL: &str = "\
+    let new_pos = project_onto_curve(point.pos + delta * point.ray);\
+\n\
+\n    return Point(\
+\n        new_pos,\
+\n        normalize(new_pos - point.pos)\
+\n    );\
+";
+
+/// Swaps out the body of the wgsl fn with the given header, the fn has to end with a `}` at the start of a line
+fn replace_fn_body(wgsl: &mut String, header: &str, body: &str) -> Option<()> {
+    let start = wgsl.find(header)? + header.len();
+    let end = start + wgsl[start..].find("\n}")?;
+
+    wgsl.replace_range(start..end, &format!("\n    {}", body));
+
+    Some(())
+}
+
+/// The vector perpendicular to a, b and c, with length the volume of the parallelepiped they span
+pub fn cross4(a: Vector4<f32>, b: Vector4<f32>, c: Vector4<f32>) -> Vector4<f32> {
+    let minor = |i: usize, j: usize, k: usize| Matrix3::new(
+        a[i], a[j], a[k],
+        b[i], b[j], b[k],
+        c[i], c[j], c[k]
+    ).determinant();
+
+    Vector4::new(minor(1, 2, 3), -minor(0, 2, 3), minor(0, 1, 3), -minor(0, 1, 2))
+}
+
 pub struct Point {
     pub pos: Vector4<f32>,
     pub ray: Vector4<f32>
@@ -24,7 +122,30 @@ pub trait Manifold: std::fmt::Debug {
 
     fn project_onto(&self, pos: Vector4<f32>) -> Vector4<f32>;
 
+    /// The projection as an expression, manifolds that have one generate their wgsl from it with `projection_wgsl`
+    /// and keep a closed form project_onto for speed, wrapped in `expression::nudged` so both nudge the same points.
+    /// The expression tests check the closed forms against it. Hyperplane, Hypersphere, Hypersphube, Ditorus and
+    /// Tiger have one
+    fn projection_expr(&self) -> Option<Expr> {
+        None
+    }
+
     fn advance_point(&self, point: Point, delta: f32) -> Point {
+        self.advance_frame(point, &mut [], delta)
+    }
+
+    /// advance_point that also carries the directions in frame, like the camera's axes, across identifications
+    fn advance_frame(&self, point: Point, frame: &mut [Vector4<f32>], delta: f32) -> Point {
+        self.identify(self.step_along_curve(point, delta), frame)
+    }
+
+    /// A step of length delta from point, embedded manifolds step off the surface and project back
+    /// onto it while intrinsic ones follow their geodesics
+    fn step_along_curve(&self, point: Point, delta: f32) -> Point {
+        if let Some(new_point) = self.geodesic(&point, delta) {
+            return new_point;
+        }
+
         let new_pos = self.project_onto(
             point.pos + delta * point.ray
         );
@@ -35,6 +156,184 @@ pub trait Manifold: std::fmt::Debug {
         }
     }
 
+    /// The body of `fn step_along_curve(point: Point, delta: f32) -> Point` in the shader
+    fn step_along_curve_wgsl(&self) -> String {
+        match self.geodesic_wgsl() {
+            Some(_) => String::from("return geodesic(point, delta);"),
+            None => String::from(STEP_ALONG_CURVE_WGSL)
+        }
+    }
+
+    /// The point distance t along the geodesic leaving point.pos towards point.ray, for manifolds that have a closed form
+    fn geodesic(&self, _point: &Point, _t: f32) -> Option<Point> {
+        None
+    }
+
+    /// The body of `fn geodesic(point: Point, t: f32) -> Point` in the shader, rays follow it instead of
+    /// step_along_curve when there is one. The steps stay info.delta long so they don't jump over the markers
+    fn geodesic_wgsl(&self) -> Option<String> {
+        None
+    }
+
+    /// For quotient spaces, glues a point that has left the fundamental domain back into it and maps
+    /// its ray and the directions in frame along with it
+    fn identify(&self, point: Point, _frame: &mut [Vector4<f32>]) -> Point {
+        point
+    }
+
+    /// The body of `fn identify_point(point: Point) -> Point` in the shader
+    fn identify_wgsl(&self) -> String {
+        String::from("return point;")
+    }
+
+    /// The unit normal at the closest point to pos. Analytic normals point out of the shape, the default
+    /// differentiates project_onto and points towards pos, so its sign is arbitrary on the manifold
+    fn normal_at(&self, pos: Vector4<f32>) -> Vector4<f32> {
+        let on_curve = self.project_onto(pos);
+
+        // The jacobian of the projection maps onto the tangent space, so its three longest columns span it
+        let mut columns: Vec<Vector4<f32>> = (0..4).map(|i| {
+            let delta = NORMAL_DELTA * Vector4::ith(i, 1.0);
+
+            self.project_onto(on_curve + delta) - self.project_onto(on_curve - delta)
+        }).collect();
+        let shortest = (0..4)
+            .min_by(|&i, &j| columns[i].magnitude_squared().total_cmp(&columns[j].magnitude_squared()))
+            .unwrap();
+
+        columns.swap_remove(shortest);
+
+        let normal = cross4(columns[0], columns[1], columns[2]).normalize();
+
+        if normal.dot(&(pos - on_curve)) < 0.0 { -normal } else { normal }
+    }
+
+    /// The body of `fn normal_at(pos: vec4<f32>) -> vec4<f32>` in the shader
+    fn normal_at_wgsl(&self) -> String {
+        String::from(NORMAL_AT_WGSL)
+    }
+
+    /// The inner product of tangent vectors at pos, the euclidean dot product for embedded manifolds
+    fn inner_product(&self, _pos: Vector4<f32>, a: Vector4<f32>, b: Vector4<f32>) -> f32 {
+        a.dot(&b)
+    }
+
+    /// An orthonormal basis of the tangent space at the closest point to pos
+    fn tangent_basis(&self, pos: Vector4<f32>) -> [Vector4<f32>; 3] {
+        let normal = self.normal_at(pos);
+        let most_normal = normal.iamax();
+        let mut basis = [Vector4::zeros(); 3];
+
+        // The axes minus their normal parts, skipping the one closest to the normal
+        for (count, i) in (0..4).filter(|&i| i != most_normal).enumerate() {
+            let mut axis = Vector4::ith(i, 1.0) - normal[i] * normal;
+
+            for previous in basis[..count].iter() {
+                axis -= axis.dot(previous) * previous;
+            }
+
+            basis[count] = axis.normalize();
+        }
+
+        basis
+    }
+
+    /// Carries a tangent vector from a nearby point over to the tangent space at pos, keeping its length. Projecting out
+    /// the new normal is the Levi-Civita connection to first order, so this is parallel transport over small steps
+    fn parallel_transport(&self, vector: Vector4<f32>, pos: Vector4<f32>) -> Vector4<f32> {
+        let normal = self.normal_at(pos);
+        let tangent = vector - normal * normal.dot(&vector);
+
+        tangent * vector.magnitude() / tangent.magnitude().max(1e-12)
+    }
+
+    /// Coordinates of a point on the manifold that follow its shape, like angles around a torus. The default
+    /// treats the manifold as a graph over x, y and z, which is exact for the Hyperplane
+    fn intrinsic_coords(&self, pos: Vector4<f32>) -> Vector3<f32> {
+        pos.xyz()
+    }
+
+    /// The body of `fn intrinsic_coords(pos: vec4<f32>) -> vec3<f32>` in the shader
+    fn intrinsic_coords_wgsl(&self) -> String {
+        String::from("return pos.xyz;")
+    }
+
+    /// The point on the manifold with the given intrinsic_coords
+    fn point_at_intrinsic_coords(&self, coords: Vector3<f32>) -> Vector4<f32> {
+        self.project_onto(Vector4::new(coords.x, coords.y, coords.z, 0.0))
+    }
+
+    /// The body of `fn point_at_intrinsic_coords(coords: vec3<f32>) -> vec4<f32>` in the shader
+    fn point_at_intrinsic_coords_wgsl(&self) -> String {
+        String::from("return project_onto_curve(vec4(coords, 0.0));")
+    }
+
+    /// Whether pos is on the open side of every wall, rays stop and the camera can't move where it isn't
+    fn in_bounds(&self, _pos: Vector4<f32>) -> bool {
+        true
+    }
+
+    /// The body of `fn in_bounds(pos: vec4<f32>) -> bool` in the shader
+    fn in_bounds_wgsl(&self) -> String {
+        String::from("return true;")
+    }
+
+    /// Where the geodesic leaving pos along tangent ends up after its length, with the ray it arrives along.
+    /// The default walks it with advance_point, so closed form geodesics are followed exactly
+    fn exp_map(&self, pos: Vector4<f32>, tangent: Vector4<f32>) -> Point {
+        let pos = self.project_onto(pos);
+        let tangent = self.parallel_transport(tangent, pos);
+        let length = self.inner_product(pos, tangent, tangent).max(0.0).sqrt();
+
+        if length < 1e-6 {
+            return Point { pos, ray: tangent };
+        }
+
+        let steps = (length / EXP_MAP_STEP).ceil().max(1.0);
+        let mut point = Point { pos, ray: tangent / length };
+
+        for _ in 0..steps as usize {
+            point = self.advance_point(point, length / steps);
+        }
+
+        point
+    }
+
+    /// The tangent vector at from whose exp_map lands on to, found by shooting geodesics and correcting the aim
+    /// with Newton's method on a finite difference jacobian. None if it doesn't converge, like past conjugate points
+    fn log_map(&self, from: Vector4<f32>, to: Vector4<f32>) -> Option<Vector4<f32>> {
+        let from = self.project_onto(from);
+        let to = self.project_onto(to);
+        let basis = self.tangent_basis(from);
+        let tangent = |c: Vector3<f32>| c.x * basis[0] + c.y * basis[1] + c.z * basis[2];
+
+        // Starts aiming straight at the target
+        let mut coordinates = Vector3::from_fn(|i, _| basis[i].dot(&(to - from)));
+
+        for _ in 0..LOG_MAP_ITERATIONS {
+            let landing = self.exp_map(from, tangent(coordinates)).pos;
+            let residual = landing - to;
+
+            if residual.magnitude() < LOG_MAP_TOLERANCE {
+                return Some(tangent(coordinates));
+            }
+
+            let h = NORMAL_DELTA.max(1e-3 * coordinates.magnitude());
+            let jacobian = Matrix4x3::from_columns(&[0, 1, 2].map(|i| {
+                let nudge = Vector3::ith(i, h);
+
+                (self.exp_map(from, tangent(coordinates + nudge)).pos - self.exp_map(from, tangent(coordinates - nudge)).pos) / (2.0 * h)
+            }));
+
+            // The least squares correction, the landing point only moves within the manifold
+            let correction = (jacobian.transpose() * jacobian).try_inverse()? * jacobian.transpose() * residual;
+
+            coordinates -= correction;
+        }
+
+        None
+    }
+
     fn is_on_curve(&self, pos: Vector4<f32>) -> bool {
         panic!("{} does not currently impl is_on_curve", self.description())
     }
@@ -44,6 +343,18 @@ pub trait Manifold: std::fmt::Debug {
     fn insert_into_wgsl(&self, mut wgsl: String) -> Option<String> {
         const FN_HEADER: &'static str = "fn project_onto_curve(pos: vec4<f32>) -> vec4<f32> {";
 
+        replace_fn_body(&mut wgsl, "fn identify_point(point: Point) -> Point {", &self.identify_wgsl())?;
+        replace_fn_body(&mut wgsl, "fn step_along_curve(point: Point, delta: f32) -> Point {", &self.step_along_curve_wgsl())?;
+        replace_fn_body(&mut wgsl, "fn normal_at(pos: vec4<f32>) -> vec4<f32> {", &self.normal_at_wgsl())?;
+        replace_fn_body(&mut wgsl, "fn in_bounds(pos: vec4<f32>) -> bool {", &self.in_bounds_wgsl())?;
+        replace_fn_body(&mut wgsl, "fn intrinsic_coords(pos: vec4<f32>) -> vec3<f32> {", &self.intrinsic_coords_wgsl())?;
+        replace_fn_body(&mut wgsl, "fn point_at_intrinsic_coords(coords: vec3<f32>) -> vec4<f32> {", &self.point_at_intrinsic_coords_wgsl())?;
+
+        if let Some(geodesic_wgsl) = self.geodesic_wgsl() {
+            replace_fn_body(&mut wgsl, "fn geodesic(point: Point, t: f32) -> Point {", &geodesic_wgsl)?;
+            replace_fn_body(&mut wgsl, "fn follow_curve(point: Point) -> Point {", "return identify_point(geodesic(point, info.delta));")?;
+        }
+
         if let Some(index) = wgsl.find(FN_HEADER) {
             wgsl.insert_str(index + FN_HEADER.len(), format!("\n    {}", self.project_onto_wgsl()).as_str());
 
@@ -61,6 +372,18 @@ pub trait Manifold: std::fmt::Debug {
 #[cfg(test)]
 mod tests {
     use crate::manifold::shapes4D::*;
+    use crate::manifold::implicit::*;
+    use crate::manifold::shapes3D::*;
+    use crate::manifold::shapes2D::*;
+    use crate::manifold::shapes1D::*;
+    use crate::manifold::product::*;
+    use crate::manifold::transformed::*;
+    use crate::manifold::heightfield::*;
+    use crate::manifold::tube::*;
+    use crate::manifold::csg::*;
+    use crate::manifold::quotient::*;
+    use crate::manifold::intrinsic::*;
+    use crate::manifold::integrator::*;
     use super::*;
 
     #[test]
@@ -70,6 +393,73 @@ mod tests {
             Box::new(Hypersphere::new(3.0)),
             Box::new(Hypersphube::new(3.0, 6.0)),
             Box::new(Ditorus::new(4.0, 2.0, 1.0)),
+            Box::new(Hyperellipsoid::new(Vector4::new(3.0, 2.0, 4.0, 2.5))),
+            Box::new(Superellipsoid::new(Vector4::new(3.0, 2.0, 4.0, 2.5), 4.0, SuperellipsoidProjection::Closest)),
+            Box::new(Superellipsoid::new(Vector4::new(3.0, 4.0, 3.0, 5.0), 1.5, SuperellipsoidProjection::Closest)),
+            Box::new(Superellipsoid::new(Vector4::new(3.0, 2.0, 4.0, 2.5), 3.0, SuperellipsoidProjection::Radial)),
+            Box::new(Tiger::new(4.0, 3.0, 1.0, TigerPairing::XyZw, true)),
+            Box::new(Tiger::new(4.0, 3.0, 1.0, TigerPairing::XzWy, false)),
+            Box::new(Tiger::new(4.0, 3.0, 1.0, TigerPairing::XwZy, true)),
+            Box::new(ExtrudedShape::new(Sphere::new(3.0), ExtrusionAxis::Z, None)),
+            Box::new(ExtrudedShape::new(Sphere::new(3.0), ExtrusionAxis::X, Some(6.0))),
+            Box::new(ExtrudedShape::new(Sphere::new(3.0), ExtrusionAxis::Direction(Vector4::new(1.0, 1.0, 1.0, 0.0)), Some(4.0))),
+            Box::new(SpunShape::new(Sphere::new(2.0), 5.0, SpinPlane::Xy)),
+            Box::new(SpunShape::new(Sphere::new(2.0), 5.0, SpinPlane::Yz)),
+            Box::new(ImplicitManifold::new(
+                |pos| pos.magnitude() - 3.0,
+                "return length(pos) - 3.0;",
+                Vector4::w() * 3.0
+            )),
+            Box::new(ImplicitManifold::new(
+                |pos| ((pos.x.powi(2) + pos.w.powi(2)).sqrt() - 4.0).powi(2) + pos.y.powi(2) + pos.z.powi(2) - 1.0,
+                "return pow(length(pos.xw) - 4.0, 2.0) + pos.y * pos.y + pos.z * pos.z - 1.0;",
+                Vector4::w() * 5.0
+            )),
+            Box::new(Heightfield::from_height(Noise::new(42, 4, 2.0, 0.3))),
+            Box::new(Heightfield::new(|pos| (pos.x * 0.5).sin() * (pos.z * 0.5).cos(), "return sin(pos.x * 0.5) * cos(pos.z * 0.5);")),
+            Box::new(TubeAroundCurve::torus_knot(2, 3, 4.0, 1.5, 48, 0.5)),
+            Box::new(TubeAroundCurve::lissajous(Vector4::new(4.0, 4.0, 3.0, 3.0), Vector4::new(1.0, 2.0, 3.0, 1.0), Vector4::new(0.0, 0.5, 1.0, 1.5), 40, 0.7)),
+            Box::new(Transformed::new(
+                TubeAroundCurve::new(vec![Vector4::zeros(), Vector4::new(4.0, 0.0, 0.0, 1.0), Vector4::new(4.0, 4.0, 0.0, 0.0), Vector4::new(0.0, 4.0, 1.0, 0.0)], 0.5),
+                plane_rotation(0, 3, 0.4),
+                Vector4::zeros(),
+                2.0
+            )),
+            Box::new(ImplicitManifold::from_field(SmoothUnion::new(Hypersphere::new(3.0), Ditorus::new(4.0, 2.0, 1.0), 1.0), Vector4::zeros())),
+            Box::new(ImplicitManifold::from_field(Union(Hyperplane, Hypersphere::new(2.0)), Vector4::w() * -1.0)),
+            Box::new(ImplicitManifold::from_field(Hypersphube::new(3.0, 1.5), Vector4::zeros())),
+            Box::new(ImplicitManifold::from_field(
+                Superellipsoid::new(Vector4::new(3.0, 2.0, 4.0, 3.0), 4.0, SuperellipsoidProjection::Closest),
+                Vector4::zeros()
+            )),
+            Box::new(ImplicitManifold::from_field(
+                SmoothIntersection::new(Hypersphere::new(5.0), Hyperellipsoid::new(Vector4::new(6.0, 6.0, 3.0, 8.0)), 0.5),
+                Vector4::zeros()
+            )),
+            Box::new(ImplicitManifold::from_field(Difference(Hypersphere::new(4.0), Intersection(Hyperplane, Hypersphere::new(2.0))), Vector4::zeros())),
+            Box::new(Quotient::klein_bottle_circle(Hyperplane, Vector3::new(4.0, 6.0, 8.0))),
+            Box::new(Hyperbolic::new(3.0)),
+            Box::new(ThreeSphere::new(3.0)),
+            Box::new(Rk4::new(Ditorus::new(4.0, 2.0, 1.0), 1e-4)),
+            Box::new(Product::new(Product2D(Line, Line), Circle::new(3.0), TigerPairing::XyZw)),
+            Box::new(Product::new(Disk::new(2.0), Circle::new(3.0), TigerPairing::XzWy)),
+            Box::new(Product::new(Circle::new(3.0), Product2D(Segment::new(-2.0, 2.0), Line), TigerPairing::XwZy)),
+            Box::new(Duocylinder::new(3.0, 2.0, TigerPairing::XyZw)),
+            Box::new(Duocylinder::new(2.0, 4.0, TigerPairing::XwZy)),
+            Box::new(ExtrudedShape::new(Torus::new(3.0, 1.0), ExtrusionAxis::Z, Some(4.0))),
+            Box::new(ExtrudedShape::new(Ellipsoid::new(Vector3::new(3.0, 1.5, 2.0)), ExtrusionAxis::Z, Some(4.0))),
+            Box::new(ExtrudedShape::new(Cylinder::new(2.0, 5.0), ExtrusionAxis::X, Some(6.0))),
+            Box::new(ExtrudedShape::new(Capsule::new(1.5, 3.0), ExtrusionAxis::Y, Some(5.0))),
+            Box::new(ExtrudedShape::new(Superquadric::new(Vector3::new(2.0, 3.0, 2.5), 4.0), ExtrusionAxis::Z, Some(3.0))),
+            Box::new(ExtrudedShape::new(RoundedBox::new(Vector3::new(2.0, 1.0, 1.5), 0.5), ExtrusionAxis::Z, Some(5.0))),
+            Box::new(SpunShape::new(Superquadric::new(Vector3::new(1.0, 2.0, 1.5), 1.5), 5.0, SpinPlane::Xz)),
+            Box::new(Transformed::new(Ditorus::new(4.0, 2.0, 1.0), plane_rotation(0, 1, 0.7), Vector4::zeros(), 1.0)),
+            Box::new(Transformed::new(
+                SpunShape::new(Sphere::new(2.0), 5.0, SpinPlane::Xz),
+                plane_rotation(2, 3, 0.3) * plane_rotation(0, 2, 1.2),
+                Vector4::new(1.0, -2.0, 0.0, 0.5),
+                1.5
+            )),
         ];
         for manifold in manifolds {
             println!("\n{}:", manifold.description());
@@ -89,4 +479,147 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_geodesics_match_small_steps() {
+        let manifolds: Vec<Box<dyn Manifold>> = vec![
+            Box::new(Hyperplane),
+            Box::new(Hypersphere::new(3.0)),
+            Box::new(Product::new(Circle::new(3.0), Plane, TigerPairing::XwZy)),
+            Box::new(Product::new(Product2D(Line, Line), Circle::new(2.0), TigerPairing::XyZw)),
+            Box::new(Transformed::new(Hypersphere::new(2.0), plane_rotation(1, 3, 0.4), Vector4::new(1.0, 0.0, 0.0, 0.0), 2.0))
+        ];
+
+        for manifold in manifolds {
+            let start = manifold.project_onto(Vector4::new(0.5, 0.0, 0.0, 0.0));
+            let ray = Vector4::new(0.6, 0.8, 0.0, 0.5).normalize();
+            let expected = manifold.geodesic(&Point { pos: start, ray }, 1.0).unwrap();
+            let mut pos = start;
+            let mut ray = manifold.project_onto(start + 0.001 * ray) - start;
+
+            for _ in 0..1000 {
+                let new_pos = manifold.project_onto(pos + 0.001 * ray.normalize());
+
+                ray = new_pos - pos;
+                pos = new_pos;
+            }
+
+            assert!((pos - expected.pos).magnitude() < 1e-2, "{}: {} != {}", manifold.description(), pos, expected.pos);
+            assert!((ray.normalize() - expected.ray.normalize()).magnitude() < 1e-2, "{}", manifold.description());
+        }
+    }
+
+    #[test]
+    fn test_normals_and_tangent_bases() {
+        // All of these project to the closest point, so pos - project_onto(pos) is along the normal
+        let manifolds: Vec<Box<dyn Manifold>> = vec![
+            Box::new(Hyperplane),
+            Box::new(Hypersphere::new(3.0)),
+            Box::new(Ditorus::new(4.0, 2.0, 1.0)),
+            Box::new(Tiger::new(4.0, 3.0, 1.0, TigerPairing::XzWy, false)),
+            Box::new(Product::new(Circle::new(3.0), Plane, TigerPairing::XwZy)),
+            Box::new(ImplicitManifold::new(|pos| pos.magnitude() - 3.0, "return length(pos) - 3.0;", Vector4::w() * 3.0)),
+            Box::new(Transformed::new(Ditorus::new(4.0, 2.0, 1.0), plane_rotation(0, 3, 0.7), Vector4::zeros(), 1.5))
+        ];
+
+        for manifold in manifolds {
+            for pos in [Vector4::new(1.0, 2.0, -1.0, 0.5), Vector4::new(-0.5, 0.3, 2.0, -1.0)] {
+                let normal = manifold.normal_at(pos);
+                let offset = (pos - manifold.project_onto(pos)).normalize();
+
+                assert!((normal.dot(&offset).abs() - 1.0).abs() < 1e-3, "{}: {} vs {}", manifold.description(), normal, offset);
+
+                let basis = manifold.tangent_basis(pos);
+
+                for i in 0..3 {
+                    assert!(basis[i].dot(&normal).abs() < 1e-3, "{}", manifold.description());
+
+                    for j in 0..3 {
+                        assert!((basis[i].dot(&basis[j]) - if i == j { 1.0 } else { 0.0 }).abs() < 1e-4);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_exp_and_log_maps() {
+        let sphere = Hypersphere::new(2.0);
+        let origin = Vector4::zeros();
+
+        // A quarter of a great circle of radius 2 ends up level with the center
+        let point = sphere.exp_map(origin, std::f32::consts::PI * Vector4::x());
+
+        assert!((point.pos - Vector4::new(2.0, 0.0, 0.0, 2.0)).magnitude() < 1e-4);
+        assert!((point.ray - Vector4::w()).magnitude() < 1e-4);
+
+        let tangent = sphere.log_map(origin, Vector4::new(2.0, 0.0, 0.0, 2.0)).unwrap();
+
+        assert!((tangent - std::f32::consts::PI * Vector4::x()).magnitude() < 1e-3);
+
+        // The antipode can be reached in every direction so there's no unique answer
+        assert!(sphere.log_map(origin, Vector4::new(0.0, 0.0, 0.0, 4.0)).is_none());
+
+        for tangent in [Vector4::new(1.0, -2.0, 0.5, 0.0), Vector4::new(0.1, 0.0, 0.2, 0.0), Vector4::new(-3.0, 2.0, 1.0, 0.0)] {
+            let pos = sphere.exp_map(origin, tangent).pos;
+            let log = sphere.log_map(origin, pos).unwrap();
+
+            assert!((log - tangent).magnitude() < 1e-3, "{} vs {}", log, tangent);
+        }
+
+        // Projected steps along the Ditorus still land where they were aimed
+        let ditorus = Ditorus::new(4.0, 2.0, 1.0);
+        let from = ditorus.project_onto(Vector4::new(1.0, 2.0, -1.0, 0.5));
+        let to = ditorus.exp_map(from, 0.8 * ditorus.tangent_basis(from)[1]).pos;
+        let log = ditorus.log_map(from, to).unwrap();
+
+        assert!((ditorus.exp_map(from, log).pos - to).magnitude() < 1e-3);
+    }
+
+    #[test]
+    fn test_intrinsic_coords_round_trip() {
+        let manifolds: Vec<Box<dyn Manifold>> = vec![
+            Box::new(Hyperplane),
+            Box::new(Hypersphere::new(3.0)),
+            Box::new(ThreeSphere::new(3.0)),
+            Box::new(Hyperbolic::new(2.0)),
+            Box::new(Ditorus::new(4.0, 2.0, 1.0)),
+            Box::new(ExtrudedShape::new(Torus::new(3.0, 1.0), ExtrusionAxis::Y, None)),
+            Box::new(ExtrudedShape::new(Sphere::new(3.0), ExtrusionAxis::Z, None)),
+            Box::new(Tiger::new(4.0, 3.0, 1.0, TigerPairing::XzWy, false)),
+            Box::new(Product::new(Product2D(Line, Line), Circle::new(3.0), TigerPairing::XyZw)),
+            Box::new(Product::new(Circle::new(3.0), Plane, TigerPairing::XwZy)),
+            Box::new(Duocylinder::new(3.0, 2.0, TigerPairing::XyZw)),
+            Box::new(SpunShape::new(Torus::new(2.0, 1.0), 5.0, SpinPlane::Xz)),
+            Box::new(SpunShape::new(Sphere::new(2.0), 5.0, SpinPlane::Yz)),
+            Box::new(Transformed::new(Ditorus::new(4.0, 2.0, 1.0), plane_rotation(0, 3, 0.7), Vector4::new(1.0, 0.0, 2.0, 0.0), 1.5)),
+            Box::new(Quotient::three_torus(Hyperplane, Vector3::new(4.0, 6.0, 8.0)))
+        ];
+
+        for manifold in manifolds {
+            for pos in [Vector4::new(1.0, 2.0, -1.0, 0.5), Vector4::new(-0.5, 0.3, 2.0, -1.0), Vector4::zeros()] {
+                let pos = manifold.project_onto(pos);
+                let coords = manifold.intrinsic_coords(pos);
+                let new_pos = manifold.point_at_intrinsic_coords(coords);
+
+                assert!((new_pos - pos).magnitude() < 1e-3, "{}: {} vs {}", manifold.description(), new_pos, pos);
+            }
+        }
+
+        assert_eq!(Hyperplane.intrinsic_coords(Vector4::new(1.0, 2.0, 3.0, 0.0)), Vector3::new(1.0, 2.0, 3.0));
+
+        // The origin is on the equator of the sphere, a quarter turn around phi goes to the top
+        let sphere = Hypersphere::new(3.0);
+        let half_pi = std::f32::consts::FRAC_PI_2;
+
+        assert!((sphere.intrinsic_coords(Vector4::zeros()) - Vector3::new(half_pi, half_pi, -half_pi)).magnitude() < 1e-5);
+        assert!((sphere.point_at_intrinsic_coords(Vector3::new(half_pi, half_pi, half_pi)) - Vector4::new(0.0, 0.0, 0.0, 6.0)).magnitude() < 1e-5);
+    }
+
+    #[test]
+    fn test_manifold_info_matches_shader() {
+        let shader = include_str!("../shader.wgsl");
+
+        assert!(shader.contains(&format!("data: array<vec4<f32>, {}>", MANIFOLD_DATA_LEN)));
+    }
 }
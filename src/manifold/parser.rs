@@ -0,0 +1,446 @@
+use std::fmt;
+use nalgebra::Vector4;
+use crate::manifold::expression::{BinaryOp, Expr, ExprError, ExprManifold, Params, Type, UnaryOp};
+use crate::manifold::implicit::{ExprField, ImplicitManifold};
+
+// Integer powers up to this get expanded into multiplications since wgsl's pow is undefined for negative bases
+const MAX_EXPANDED_POWER: i32 = 8;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    /// 1-based column of the character where the error is
+    pub column: usize,
+    pub message: String
+}
+
+impl ParseError {
+    fn new(column: usize, message: impl Into<String>) -> Self {
+        Self { column, message: message.into() }
+    }
+
+    /// The source with a caret under the offending column
+    pub fn pointer(&self, source: &str) -> String {
+        format!("{}\n{}^", source, " ".repeat(self.column - 1))
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "column {}: {}", self.column, self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f32),
+    Identifier(String),
+    Symbol(char)
+}
+
+fn tokenize(source: &str) -> Result<Vec<(Token, usize)>, ParseError> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let column = i + 1;
+
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit() || (c == '.' && chars.get(i + 1).is_some_and(|c| c.is_ascii_digit())) {
+            let start = i;
+
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+
+            if i < chars.len() && (chars[i] == 'e' || chars[i] == 'E') {
+                let mut j = i + 1;
+
+                if j < chars.len() && (chars[j] == '+' || chars[j] == '-') {
+                    j += 1;
+                }
+
+                if j < chars.len() && chars[j].is_ascii_digit() {
+                    i = j;
+
+                    while i < chars.len() && chars[i].is_ascii_digit() {
+                        i += 1;
+                    }
+                }
+            }
+
+            let text: String = chars[start..i].iter().collect();
+            let number = text.parse::<f32>()
+                .map_err(|_| ParseError::new(column, format!("`{}` is not a number", text)))?;
+
+            tokens.push((Token::Number(number), column));
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let start = i;
+
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+
+            tokens.push((Token::Identifier(chars[start..i].iter().collect()), column));
+        } else if "+-*/^(),=.".contains(c) {
+            tokens.push((Token::Symbol(c), column));
+            i += 1;
+        } else {
+            return Err(ParseError::new(column, format!("unexpected character `{}`", c)));
+        }
+    }
+
+    Ok(tokens)
+}
+
+// Type checks as the tree gets built so errors can point at the operator that caused them
+fn checked(expr: Expr, column: usize) -> Result<Expr, ParseError> {
+    expr.get_type(4)
+        .map(|_| expr)
+        .map_err(|error| ParseError::new(column, error.to_string()))
+}
+
+struct Parser<'a> {
+    tokens: Vec<(Token, usize)>,
+    index: usize,
+    end_column: usize,
+    params: &'a [(&'a str, f32)]
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.index).map(|(token, _)| token)
+    }
+
+    fn column(&self) -> usize {
+        self.tokens.get(self.index).map_or(self.end_column, |(_, column)| *column)
+    }
+
+    fn next(&mut self) -> Option<(Token, usize)> {
+        let token = self.tokens.get(self.index).cloned();
+        self.index += 1;
+
+        token
+    }
+
+    fn eat(&mut self, symbol: char) -> bool {
+        if self.peek() == Some(&Token::Symbol(symbol)) {
+            self.index += 1;
+
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, symbol: char) -> Result<(), ParseError> {
+        if self.eat(symbol) {
+            Ok(())
+        } else {
+            Err(ParseError::new(self.column(), format!("expected `{}`", symbol)))
+        }
+    }
+
+    // equation := sum ('=' sum)?
+    fn equation(&mut self) -> Result<Expr, ParseError> {
+        let lhs = self.sum()?;
+        let column = self.column();
+
+        if self.eat('=') {
+            let rhs = self.sum()?;
+
+            checked(lhs - rhs, column)
+        } else {
+            Ok(lhs)
+        }
+    }
+
+    // sum := product (('+' | '-') product)*
+    fn sum(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.product()?;
+
+        loop {
+            let column = self.column();
+
+            if self.eat('+') {
+                expr = checked(expr + self.product()?, column)?;
+            } else if self.eat('-') {
+                expr = checked(expr - self.product()?, column)?;
+            } else {
+                return Ok(expr);
+            }
+        }
+    }
+
+    // product := unary (('*' | '/') unary)*
+    fn product(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.unary()?;
+
+        loop {
+            let column = self.column();
+
+            if self.eat('*') {
+                expr = checked(expr * self.unary()?, column)?;
+            } else if self.eat('/') {
+                expr = checked(expr / self.unary()?, column)?;
+            } else {
+                return Ok(expr);
+            }
+        }
+    }
+
+    // unary := '-' unary | power
+    fn unary(&mut self) -> Result<Expr, ParseError> {
+        if self.eat('-') {
+            Ok(match self.unary()? {
+                Expr::Constant(value) => Expr::constant(-value),
+                expr => -expr
+            })
+        } else {
+            self.power()
+        }
+    }
+
+    // power := postfix ('^' unary)?
+    fn power(&mut self) -> Result<Expr, ParseError> {
+        let base = self.postfix()?;
+        let column = self.column();
+
+        if !self.eat('^') {
+            return Ok(base);
+        }
+
+        let exponent = self.unary()?;
+
+        match exponent {
+            Expr::Constant(n) if n.fract() == 0.0 && n.abs() <= MAX_EXPANDED_POWER as f32 => {
+                let product = (1..(n as i32).abs()).fold(
+                    if n == 0.0 { Expr::constant(1.0) } else { base.clone() },
+                    |product, _| product * base.clone()
+                );
+
+                checked(if n < 0.0 { Expr::constant(1.0) / product } else { product }, column)
+            },
+            exponent => checked(base.pow(exponent), column)
+        }
+    }
+
+    // postfix := atom ('.' swizzle)*
+    fn postfix(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.atom()?;
+
+        while self.eat('.') {
+            let column = self.column();
+
+            match self.next() {
+                Some((Token::Identifier(components), _)) if components.chars().all(|c| "xyzw".contains(c)) =>
+                    expr = checked(expr.swizzle(&components), column)?,
+                _ => return Err(ParseError::new(column, "expected a swizzle like `.xy`"))
+            }
+        }
+
+        Ok(expr)
+    }
+
+    // atom := number | identifier | identifier '(' arguments ')' | '(' sum ')'
+    fn atom(&mut self) -> Result<Expr, ParseError> {
+        let column = self.column();
+
+        match self.next() {
+            Some((Token::Number(number), _)) => Ok(Expr::constant(number)),
+            Some((Token::Symbol('('), _)) => {
+                let expr = self.sum()?;
+                self.expect(')')?;
+
+                Ok(expr)
+            },
+            Some((Token::Identifier(name), _)) if self.eat('(') => {
+                let mut arguments = Vec::new();
+
+                if !self.eat(')') {
+                    loop {
+                        arguments.push(self.sum()?);
+
+                        if self.eat(')') {
+                            break;
+                        }
+
+                        self.expect(',')?;
+                    }
+                }
+
+                let expr = call(&name, arguments)
+                    .map_err(|message| ParseError::new(column, message))?;
+
+                checked(expr, column)
+            },
+            Some((Token::Identifier(name), _)) => match name.as_str() {
+                "pos" => Ok(Expr::pos()),
+                "x" | "y" | "z" | "w" => Ok(Expr::pos().swizzle(&name)),
+                "pi" => Ok(Expr::constant(std::f32::consts::PI)),
+                _ => self.params.iter()
+                    .find(|(param, _)| *param == name)
+                    .map(|(_, value)| Expr::constant(*value))
+                    .ok_or_else(|| ParseError::new(column, format!("unknown parameter `{}`", name)))
+            },
+            Some((Token::Symbol(symbol), _)) => Err(ParseError::new(column, format!("unexpected `{}`", symbol))),
+            None => Err(ParseError::new(column, "unexpected end of formula"))
+        }
+    }
+}
+
+fn call(name: &str, mut arguments: Vec<Expr>) -> Result<Expr, String> {
+    let unary = match name {
+        "abs" => Some(UnaryOp::Abs),
+        "sqrt" => Some(UnaryOp::Sqrt),
+        "sin" => Some(UnaryOp::Sin),
+        "cos" => Some(UnaryOp::Cos),
+        "tan" => Some(UnaryOp::Tan),
+        "exp" => Some(UnaryOp::Exp),
+        "ln" | "log" => Some(UnaryOp::Log),
+        "floor" => Some(UnaryOp::Floor),
+        "normalize" => Some(UnaryOp::Normalize),
+        "length" if arguments.len() == 1 => Some(UnaryOp::Length),
+        _ => None
+    };
+    let binary = match name {
+        "pow" => Some(BinaryOp::Pow),
+        "min" => Some(BinaryOp::Min),
+        "max" => Some(BinaryOp::Max),
+        "atan2" => Some(BinaryOp::Atan2),
+        "dot" => Some(BinaryOp::Dot),
+        _ => None
+    };
+
+    match (name, unary, binary, arguments.len()) {
+        (_, Some(op), _, 1) => Ok(Expr::unary(op, arguments.remove(0))),
+        (_, _, Some(op), 2) => {
+            let b = arguments.remove(1);
+
+            Ok(Expr::binary(op, arguments.remove(0), b))
+        },
+        // length(a, b, ...) is the length of the vector (a, b, ...)
+        ("length" | "vec2" | "vec3" | "vec4", _, _, 2..=4) => {
+            let vector = Expr::vector(arguments);
+
+            Ok(if name == "length" { vector.length() } else { vector })
+        },
+        (_, Some(_), _, n) => Err(format!("`{}` takes 1 argument, not {}", name, n)),
+        (_, _, Some(_), n) => Err(format!("`{}` takes 2 arguments, not {}", name, n)),
+        ("length" | "vec2" | "vec3" | "vec4", _, _, n) => Err(format!("`{}` takes 2 to 4 arguments, not {}", name, n)),
+        _ => Err(format!("unknown function `{}`", name))
+    }
+}
+
+/// Parses a formula in terms of x, y, z, w (or the vector `pos`) and the named `params`.
+/// An `=` turns an equation `a = b` into `a - b`.
+pub fn parse_expr(source: &str, params: &[(&str, f32)]) -> Result<Expr, ParseError> {
+    if let Some((name, _)) = params.iter().find(|(name, _)| ["x", "y", "z", "w", "pos", "pi"].contains(name)) {
+        return Err(ParseError::new(1, format!("`{}` can't be used as a parameter name", name)));
+    }
+
+    let mut parser = Parser {
+        tokens: tokenize(source)?,
+        index: 0,
+        end_column: source.chars().count() + 1,
+        params
+    };
+
+    let expr = parser.equation()?;
+
+    if parser.index < parser.tokens.len() {
+        Err(ParseError::new(parser.column(), "expected the end of the formula"))
+    } else {
+        checked(expr, 1)
+    }
+}
+
+/// Parses the implicit equation of a hypersurface, ex: `(sqrt(x^2 + w^2) - R)^2 + y^2 + z^2 - r^2`
+pub fn parse_implicit(
+    source: &str,
+    params: &[(&str, f32)],
+    center: Vector4<f32>
+) -> Result<ImplicitManifold<ExprField>, ParseError> {
+    let expr = parse_expr(source, params)?;
+
+    ExprField::new(expr)
+        .map(|field| ImplicitManifold::from_field(field, center))
+        .map_err(|error| type_error(source, error))
+}
+
+/// Parses a projection that takes `pos` to the closest point on the manifold, ex: `R * normalize(pos - vec4(0, 0, 0, R)) + vec4(0, 0, 0, R)`
+pub fn parse_projection(
+    source: &str,
+    params: &[(&str, f32)],
+    description: &str
+) -> Result<ExprManifold, ParseError> {
+    let expr = parse_expr(source, params)?;
+
+    ExprManifold::new(description, expr, Params::default())
+        .map_err(|error| type_error(source, error))
+}
+
+fn type_error(source: &str, error: ExprError) -> ParseError {
+    match error {
+        ExprError::UnexpectedType { expected: Type::Scalar, found } =>
+            ParseError::new(1, format!("an implicit equation has to be a number, not {:?}", found)),
+        ExprError::UnexpectedType { expected, found } =>
+            ParseError::new(1, format!("a projection has to be a {:?}, not {:?}", expected, found)),
+        error => ParseError::new(source.chars().count() + 1, error.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::manifold::Manifold;
+    use super::*;
+
+    #[test]
+    fn test_parse_implicit() {
+        let source = "(sqrt(x^2 + w^2) - R)^2 + y^2 + z^2 - r^2";
+        let manifold = parse_implicit(source, &[("R", 4.0), ("r", 1.0)], Vector4::w() * 5.0).unwrap();
+
+        for i in -5..5 {
+            for j in -5..5 {
+                let pos = Vector4::new(i as f32, j as f32, 0.5, 0.0);
+                let pos2 = manifold.project_onto(pos);
+
+                assert!(manifold.is_on_curve(pos2), "{:?} => {:?}", pos, pos2);
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_projection() {
+        let manifold = parse_projection("3 * normalize(pos - vec4(0, 0, 0, 3)) + vec4(0, 0, 0, 3)", &[], "Sphere").unwrap();
+
+        assert!((manifold.project_onto(Vector4::new(0.0, 0.0, 1.0, 3.0)) - Vector4::new(0.0, 0.0, 3.0, 3.0)).magnitude() < 1e-5);
+
+        // The example from the docs, vectors of params stand in for a center
+        let manifold = parse_projection("R * normalize(pos - vec4(0, 0, 0, R)) + vec4(0, 0, 0, R)", &[("R", 2.0)], "Sphere").unwrap();
+
+        assert!((manifold.project_onto(Vector4::new(1.0, 0.0, 0.0, 2.0)) - Vector4::new(2.0, 0.0, 0.0, 2.0)).magnitude() < 1e-5);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(parse_expr("x + * y", &[]).unwrap_err().column, 5);
+        assert_eq!(parse_expr("x + q", &[]).unwrap_err().column, 5);
+        assert_eq!(parse_expr("sqrt(x, y)", &[]).unwrap_err().column, 1);
+        assert_eq!(parse_expr("(x + y", &[]).unwrap_err().column, 7);
+        assert_eq!(parse_expr("x + y $", &[]).unwrap_err().column, 7);
+        assert_eq!(parse_expr("x + normalize(y)", &[]).unwrap_err().column, 5);
+        assert_eq!(parse_implicit("pos", &[], Vector4::zeros()).unwrap_err().column, 1);
+    }
+
+    #[test]
+    fn test_parse_large_powers() {
+        // Exponents past i32 stay powers instead of overflowing the expansion check
+        assert!(parse_expr("x^-1e10", &[]).is_ok());
+        assert!(parse_expr("x^1e10", &[]).is_ok());
+    }
+}
@@ -0,0 +1,318 @@
+use nalgebra::{Vector2, Vector3, Vector4};
+use crate::manifold::{EPSILON, Manifold, Point};
+use crate::manifold::shapes2D::Shape2D;
+use crate::manifold::shapes4D::TigerPairing;
+
+/// The cartesian product of two Shape2Ds, each living in one of the planes of the pairing
+#[derive(Debug)]
+pub struct Product<A: Shape2D, B: Shape2D> {
+    a: A,
+    b: B,
+    pairing: TigerPairing
+}
+
+impl<A: Shape2D, B: Shape2D> Product<A, B> {
+    pub fn new(a: A, b: B, pairing: TigerPairing) -> Self {
+        Self { a, b, pairing }
+    }
+}
+
+impl<A: Shape2D, B: Shape2D> Manifold for Product<A, B> {
+    fn description(&self) -> String {
+        format!("{} x {}", self.a.description(), self.b.description())
+    }
+
+    fn project_onto_wgsl(&self) -> String {
+        let (a, b) = self.pairing.split_components();
+
+        format!("\
+                let new_a = {};\
+          \n    let new_b = {};\
+          \n    \
+          \n    return {};\
+            ",
+            self.a.project_onto_wgsl(&format!("pos.{}", a), "manifold_info.v1"),
+            self.b.project_onto_wgsl(&format!("pos.{}", b), "manifold_info.v2"),
+            self.pairing.join_wgsl("new_a", "new_b")
+        )
+    }
+
+    fn project_onto(&self, pos: Vector4<f32>) -> Vector4<f32> {
+        let (a, b) = self.pairing.split(pos);
+
+        self.pairing.join(self.a.project_onto(a), self.b.project_onto(b))
+    }
+
+    fn is_on_curve(&self, pos: Vector4<f32>) -> bool {
+        let (a, b) = self.pairing.split(pos);
+
+        self.a.is_on_curve(a) && self.b.is_on_curve(b)
+    }
+
+    /// The coords of a followed by the coords of b, like (x, y, angle) for a plane times a circle
+    fn intrinsic_coords(&self, pos: Vector4<f32>) -> Vector3<f32> {
+        let (a, b) = self.pairing.split(pos);
+
+        Vector3::from_iterator(
+            self.a.coords(a).iter().take(self.a.dimension())
+                .chain(self.b.coords(b).iter().take(self.b.dimension()))
+                .copied()
+                .chain(std::iter::repeat(0.0))
+        )
+    }
+
+    fn intrinsic_coords_wgsl(&self) -> String {
+        let (a, b) = self.pairing.split_components();
+        let components: Vec<&str> = ["coords_a.x", "coords_a.y"].into_iter().take(self.a.dimension())
+            .chain(["coords_b.x", "coords_b.y"].into_iter().take(self.b.dimension()))
+            .chain(std::iter::repeat("0.0"))
+            .take(3)
+            .collect();
+
+        format!("\
+                let coords_a = {};\
+          \n    let coords_b = {};\
+          \n    \
+          \n    return vec3({});\
+            ",
+            self.a.coords_wgsl(&format!("pos.{}", a), "manifold_info.v1"),
+            self.b.coords_wgsl(&format!("pos.{}", b), "manifold_info.v2"),
+            components.join(", ")
+        )
+    }
+
+    fn point_at_intrinsic_coords(&self, coords: Vector3<f32>) -> Vector4<f32> {
+        let part = |start: usize, dimension: usize| Vector2::from_fn(|i, _| {
+            if i < dimension && start + i < 3 { coords[start + i] } else { 0.0 }
+        });
+
+        self.pairing.join(
+            self.a.point_at_coords(part(0, self.a.dimension())),
+            self.b.point_at_coords(part(self.a.dimension(), self.b.dimension()))
+        )
+    }
+
+    fn point_at_intrinsic_coords_wgsl(&self) -> String {
+        let part = |start: usize, dimension: usize| {
+            let component = |i: usize| if i < dimension && start + i < 3 { ["coords.x", "coords.y", "coords.z"][start + i] } else { "0.0" };
+
+            format!("vec2({}, {})", component(0), component(1))
+        };
+
+        format!("\
+                let new_a = {};\
+          \n    let new_b = {};\
+          \n    \
+          \n    return {};\
+            ",
+            self.a.point_at_coords_wgsl(&part(0, self.a.dimension()), "manifold_info.v1"),
+            self.b.point_at_coords_wgsl(&part(self.a.dimension(), self.b.dimension()), "manifold_info.v2"),
+            self.pairing.join_wgsl("new_a", "new_b")
+        )
+    }
+
+    /// Each factor follows its own geodesic, at the speed of its part of the ray once the ray is made tangent
+    fn geodesic(&self, point: &Point, t: f32) -> Option<Point> {
+        let (a, b) = self.pairing.split(point.pos);
+        let (a_vel, b_vel) = self.pairing.split(point.ray);
+
+        // At t = 0 the factors give back the tangent part of their velocities
+        let speed = self.pairing.join(self.a.geodesic(a, a_vel, 0.0)?.1, self.b.geodesic(b, b_vel, 0.0)?.1).magnitude().max(1e-6);
+        let (new_a, new_a_vel) = self.a.geodesic(a, a_vel, t / speed)?;
+        let (new_b, new_b_vel) = self.b.geodesic(b, b_vel, t / speed)?;
+
+        Some(Point {
+            pos: self.pairing.join(new_a, new_b),
+            ray: self.pairing.join(new_a_vel, new_b_vel)
+        })
+    }
+
+    fn geodesic_wgsl(&self) -> Option<String> {
+        let (a, b) = self.pairing.split_components();
+
+        Some(format!("\
+                return product_geodesic(point, t);\
+          \n}}\
+          \n\
+          \n{}\
+          \n\
+          \n{}\
+          \n\
+          \nfn product_geodesic(point: Point, t: f32) -> Point {{\
+          \n    let a = point.pos.{a};\
+          \n    let b = point.pos.{b};\
+          \n    let a_vel = point.ray.{a};\
+          \n    let b_vel = point.ray.{b};\
+          \n    let speed = max(length(vec4(geodesic_a(a, a_vel, 0.0, manifold_info.v1).zw, geodesic_b(b, b_vel, 0.0, manifold_info.v2).zw)), 1e-6);\
+          \n    let new_a = geodesic_a(a, a_vel, t / speed, manifold_info.v1);\
+          \n    let new_b = geodesic_b(b, b_vel, t / speed, manifold_info.v2);\
+          \n    \
+          \n    return Point({}, {});\
+            ",
+            self.a.geodesic_wgsl("geodesic_a")?,
+            self.b.geodesic_wgsl("geodesic_b")?,
+            self.pairing.join_wgsl("new_a.xy", "new_b.xy"),
+            self.pairing.join_wgsl("new_a.zw", "new_b.zw"),
+            a = a, b = b
+        ))
+    }
+
+    fn get_bytes(&self) -> Vec<u8> {
+        bytemuck::cast_slice(&[
+            self.a.get_data().as_slice(),
+            self.b.get_data().as_slice(),
+            Vector4::zeros().as_slice(),
+            &[0.0, 0.0, 0.0]
+        ].concat()[..]).to_owned()
+    }
+}
+
+/// The boundary of the product of two disks, made of a circle x disk and a disk x circle glued along their
+/// shared clifford torus, the closest point is the nearer of the closest points on the two pieces
+#[derive(Debug)]
+pub struct Duocylinder {
+    radius1: f32,
+    radius2: f32,
+    pairing: TigerPairing,
+    center: Vector4<f32>
+}
+
+impl Duocylinder {
+    pub fn new(radius1: f32, radius2: f32, pairing: TigerPairing) -> Self {
+        Self {
+            radius1,
+            radius2,
+            pairing,
+            center: radius2 * pairing.join(Vector2::zeros(), Vector2::y())
+        }
+    }
+}
+
+fn project_onto_disk(pos: Vector2<f32>, radius: f32) -> Vector2<f32> {
+    if pos.magnitude() > radius {
+        radius * pos.normalize()
+    } else {
+        pos
+    }
+}
+
+impl Manifold for Duocylinder {
+    fn description(&self) -> String {
+        String::from("Duocylinder")
+    }
+
+    fn project_onto_wgsl(&self) -> String {
+        let (a, b) = self.pairing.split_components();
+
+        format!("\
+                let new_pos = pos - manifold_info.v1;\
+          \n    let a = new_pos.{};\
+          \n    let b = new_pos.{};\
+          \n    let r1 = manifold_info.r1;\
+          \n    let r2 = manifold_info.r2;\
+          \n    \
+          \n    let a1 = r1 * normalize(a);\
+          \n    let b1 = select(b, r2 * normalize(b), length(b) > r2);\
+          \n    let a2 = select(a, r1 * normalize(a), length(a) > r1);\
+          \n    let b2 = r2 * normalize(b);\
+          \n    \
+          \n    let side1 = {};\
+          \n    let side2 = {};\
+          \n    \
+          \n    return select(side2, side1, distance(side1, new_pos) < distance(side2, new_pos)) + manifold_info.v1;\
+            ", a, b, self.pairing.join_wgsl("a1", "b1"), self.pairing.join_wgsl("a2", "b2"))
+    }
+
+    fn project_onto(&self, pos: Vector4<f32>) -> Vector4<f32> {
+        let new_pos = pos - self.center;
+        let (mut a, mut b) = self.pairing.split(new_pos);
+
+        if a.magnitude() == 0.0 {
+            a.x += 0.0001;
+        }
+
+        if b.magnitude() == 0.0 {
+            b.x += 0.0001;
+        }
+
+        let side1 = self.pairing.join(self.radius1 * a.normalize(), project_onto_disk(b, self.radius2));
+        let side2 = self.pairing.join(project_onto_disk(a, self.radius1), self.radius2 * b.normalize());
+
+        if (side1 - new_pos).magnitude() < (side2 - new_pos).magnitude() {
+            side1 + self.center
+        } else {
+            side2 + self.center
+        }
+    }
+
+    fn is_on_curve(&self, pos: Vector4<f32>) -> bool {
+        let (a, b) = self.pairing.split(pos - self.center);
+        let (a, b) = (a.magnitude(), b.magnitude());
+
+        ((a - self.radius1).abs() < EPSILON && b < self.radius2 + EPSILON) ||
+            ((b - self.radius2).abs() < EPSILON && a < self.radius1 + EPSILON)
+    }
+
+    /// The angles around the planes a and b, then the distance from the center of the disk on the circle x disk
+    /// side, which carries on from radius2 across the clifford torus to radius1 + radius2 at the center of the other disk
+    fn intrinsic_coords(&self, pos: Vector4<f32>) -> Vector3<f32> {
+        let (a, b) = self.pairing.split(pos - self.center);
+        let distance = if a.magnitude() - self.radius1 >= b.magnitude() - self.radius2 {
+            b.magnitude()
+        } else {
+            self.radius1 + self.radius2 - a.magnitude()
+        };
+
+        Vector3::new(a.y.atan2(a.x), b.y.atan2(b.x), distance)
+    }
+
+    fn intrinsic_coords_wgsl(&self) -> String {
+        let (a, b) = self.pairing.split_components();
+
+        format!("\
+                let q = pos - manifold_info.v1;\
+          \n    let a = q.{};\
+          \n    let b = q.{};\
+          \n    let r1 = manifold_info.r1;\
+          \n    let r2 = manifold_info.r2;\
+          \n    let distance = select(r1 + r2 - length(a), length(b), length(a) - r1 >= length(b) - r2);\
+          \n    \
+          \n    return vec3(atan2(a.y, a.x), atan2(b.y, b.x), distance);\
+            ", a, b)
+    }
+
+    fn point_at_intrinsic_coords(&self, coords: Vector3<f32>) -> Vector4<f32> {
+        let (length_a, length_b) = if coords.z <= self.radius2 {
+            (self.radius1, coords.z)
+        } else {
+            (self.radius1 + self.radius2 - coords.z, self.radius2)
+        };
+
+        self.pairing.join(
+            length_a * Vector2::new(coords.x.cos(), coords.x.sin()),
+            length_b * Vector2::new(coords.y.cos(), coords.y.sin())
+        ) + self.center
+    }
+
+    fn point_at_intrinsic_coords_wgsl(&self) -> String {
+        format!("\
+                let r1 = manifold_info.r1;\
+          \n    let r2 = manifold_info.r2;\
+          \n    let length_a = select(r1 + r2 - coords.z, r1, coords.z <= r2);\
+          \n    let length_b = min(coords.z, r2);\
+          \n    let a = length_a * vec2(cos(coords.x), sin(coords.x));\
+          \n    let b = length_b * vec2(cos(coords.y), sin(coords.y));\
+          \n    \
+          \n    return {} + manifold_info.v1;\
+            ", self.pairing.join_wgsl("a", "b"))
+    }
+
+    fn get_bytes(&self) -> Vec<u8> {
+        bytemuck::cast_slice(&[
+            self.center.as_slice(),
+            Vector4::zeros().as_slice(),
+            Vector4::zeros().as_slice(),
+            &[self.radius1, self.radius2, 0.0]
+        ].concat()[..]).to_owned()
+    }
+}
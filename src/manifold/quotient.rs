@@ -0,0 +1,229 @@
+use nalgebra::{Vector3, Vector4};
+use winit::event::VirtualKeyCode;
+use crate::manifold::{Manifold, Point};
+
+/// Glues the faces of the fundamental domain at pos[axis] = ±period / 2 together, so leaving through one face
+/// brings you back in through the other. With a flip the gluing is a glide reflection that also mirrors the flip axis
+#[derive(Debug, Clone, Copy)]
+pub struct Identification {
+    pub axis: usize,
+    pub period: f32,
+    pub flip: Option<usize>
+}
+
+impl Identification {
+    pub fn translation(axis: usize, period: f32) -> Self {
+        Self { axis, period, flip: None }
+    }
+
+    pub fn glide_reflection(axis: usize, period: f32, flip: usize) -> Self {
+        Self { axis, period, flip: Some(flip) }
+    }
+
+    /// How many periods pos is away from the fundamental domain along axis
+    fn periods(&self, pos: Vector4<f32>) -> f32 {
+        (pos[self.axis] / self.period).round()
+    }
+}
+
+/// A manifold with points identified by translations and glide reflections of the coordinates, rays that leave
+/// the fundamental domain (the box centered on the origin) wrap around to the other side.
+/// The manifold has to be symmetric under the identifications, like the Hyperplane is under all of them.
+#[derive(Debug)]
+pub struct Quotient<M: Manifold> {
+    manifold: M,
+    identifications: Vec<Identification>
+}
+
+impl<M: Manifold> Quotient<M> {
+    pub fn new(manifold: M, identifications: Vec<Identification>) -> Self {
+        for identification in identifications.iter() {
+            assert!(identification.axis < 4 && identification.flip.is_none_or(|flip| flip < 4), "axes must be less than 4");
+            assert!(identification.period > 0.0, "period must be positive");
+            assert_ne!(Some(identification.axis), identification.flip, "a glide reflection can't flip its own axis");
+        }
+
+        Self { manifold, identifications }
+    }
+
+    /// The flat 3-torus, wrapping x, y and z with the given periods
+    pub fn three_torus(manifold: M, periods: Vector3<f32>) -> Self {
+        Self::new(manifold, vec![
+            Identification::translation(0, periods.x),
+            Identification::translation(1, periods.y),
+            Identification::translation(2, periods.z)
+        ])
+    }
+
+    /// The Klein bottle times a circle, wrapping x with y mirrored and wrapping y and z normally
+    pub fn klein_bottle_circle(manifold: M, periods: Vector3<f32>) -> Self {
+        Self::new(manifold, vec![
+            Identification::glide_reflection(0, periods.x, 1),
+            Identification::translation(1, periods.y),
+            Identification::translation(2, periods.z)
+        ])
+    }
+
+    pub fn manifold(&self) -> &M {
+        &self.manifold
+    }
+}
+
+impl<M: Manifold> Manifold for Quotient<M> {
+    fn description(&self) -> String {
+        format!("{} with {} identifications", self.manifold.description(), self.identifications.len())
+    }
+
+    fn project_onto_wgsl(&self) -> String {
+        self.manifold.project_onto_wgsl()
+    }
+
+    fn project_onto(&self, pos: Vector4<f32>) -> Vector4<f32> {
+        self.manifold.project_onto(pos)
+    }
+
+    fn step_along_curve(&self, point: Point, delta: f32) -> Point {
+        self.manifold.step_along_curve(point, delta)
+    }
+
+    fn step_along_curve_wgsl(&self) -> String {
+        self.manifold.step_along_curve_wgsl()
+    }
+
+    fn geodesic(&self, point: &Point, t: f32) -> Option<Point> {
+        self.manifold.geodesic(point, t)
+    }
+
+    fn geodesic_wgsl(&self) -> Option<String> {
+        self.manifold.geodesic_wgsl()
+    }
+
+    fn normal_at(&self, pos: Vector4<f32>) -> Vector4<f32> {
+        self.manifold.normal_at(pos)
+    }
+
+    fn normal_at_wgsl(&self) -> String {
+        self.manifold.normal_at_wgsl()
+    }
+
+    fn inner_product(&self, pos: Vector4<f32>, a: Vector4<f32>, b: Vector4<f32>) -> f32 {
+        self.manifold.inner_product(pos, a, b)
+    }
+
+    fn tangent_basis(&self, pos: Vector4<f32>) -> [Vector4<f32>; 3] {
+        self.manifold.tangent_basis(pos)
+    }
+
+    fn parallel_transport(&self, vector: Vector4<f32>, pos: Vector4<f32>) -> Vector4<f32> {
+        self.manifold.parallel_transport(vector, pos)
+    }
+
+    fn intrinsic_coords(&self, pos: Vector4<f32>) -> Vector3<f32> {
+        self.manifold.intrinsic_coords(pos)
+    }
+
+    fn intrinsic_coords_wgsl(&self) -> String {
+        self.manifold.intrinsic_coords_wgsl()
+    }
+
+    fn point_at_intrinsic_coords(&self, coords: Vector3<f32>) -> Vector4<f32> {
+        self.manifold.point_at_intrinsic_coords(coords)
+    }
+
+    fn point_at_intrinsic_coords_wgsl(&self) -> String {
+        self.manifold.point_at_intrinsic_coords_wgsl()
+    }
+
+    fn in_bounds(&self, pos: Vector4<f32>) -> bool {
+        self.manifold.in_bounds(pos)
+    }
+
+    fn in_bounds_wgsl(&self) -> String {
+        self.manifold.in_bounds_wgsl()
+    }
+
+    fn identify(&self, mut point: Point, frame: &mut [Vector4<f32>]) -> Point {
+        for identification in self.identifications.iter() {
+            let periods = identification.periods(point.pos);
+
+            point.pos[identification.axis] -= periods * identification.period;
+
+            if let Some(flip) = identification.flip {
+                if periods.rem_euclid(2.0) == 1.0 {
+                    point.pos[flip] *= -1.0;
+                    point.ray[flip] *= -1.0;
+
+                    for direction in frame.iter_mut() {
+                        direction[flip] *= -1.0;
+                    }
+                }
+            }
+        }
+
+        point
+    }
+
+    fn identify_wgsl(&self) -> String {
+        let mut wgsl = String::from("var pos = point.pos;\n    var ray = point.ray;\n");
+
+        for identification in self.identifications.iter() {
+            wgsl += &format!("\
+                \n    {{\
+                \n        let periods = round(pos[{0}] / {1:?});\
+                \n        pos[{0}] -= periods * {1:?};\
+                ",
+                identification.axis,
+                identification.period
+            );
+
+            if let Some(flip) = identification.flip {
+                wgsl += &format!("\
+                    \n        if (abs(periods % 2.0) == 1.0) {{\
+                    \n            pos[{0}] = -pos[{0}];\
+                    \n            ray[{0}] = -ray[{0}];\
+                    \n        }}\
+                    ",
+                    flip
+                );
+            }
+
+            wgsl += "\n    }";
+        }
+
+        wgsl + "\n\n    return Point(pos, ray);"
+    }
+
+    fn is_on_curve(&self, pos: Vector4<f32>) -> bool {
+        self.manifold.is_on_curve(pos)
+    }
+
+    fn get_bytes(&self) -> Vec<u8> {
+        self.manifold.get_bytes()
+    }
+
+    fn change_on_keybinds(&mut self, key_code: &VirtualKeyCode) {
+        self.manifold.change_on_keybinds(key_code);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::manifold::shapes4D::Hyperplane;
+    use super::*;
+
+    #[test]
+    fn test_rays_wrap_around() {
+        let torus = Quotient::three_torus(Hyperplane, Vector3::new(4.0, 6.0, 8.0));
+        let point = torus.advance_point(Point { pos: Vector4::new(1.9, 2.0, 0.0, 0.0), ray: Vector4::x() }, 0.2);
+
+        assert!((point.pos - Vector4::new(-1.9, 2.0, 0.0, 0.0)).magnitude() < 1e-4);
+        assert!((point.ray - Vector4::x()).magnitude() < 1e-4);
+
+        let klein = Quotient::klein_bottle_circle(Hyperplane, Vector3::new(4.0, 6.0, 8.0));
+        let mut frame = [Vector4::x(), Vector4::y(), Vector4::z()];
+        let point = klein.advance_frame(Point { pos: Vector4::new(1.9, 2.0, 0.0, 0.0), ray: Vector4::x() }, &mut frame, 0.2);
+
+        assert!((point.pos - Vector4::new(-1.9, -2.0, 0.0, 0.0)).magnitude() < 1e-4);
+        assert_eq!(frame, [Vector4::x(), -Vector4::y(), Vector4::z()]);
+    }
+}
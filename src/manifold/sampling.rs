@@ -0,0 +1,154 @@
+use nalgebra::Vector4;
+use crate::manifold::Manifold;
+
+/// How far from the manifold ambient samples can be and still get projected onto it
+const SHELL_THICKNESS: f32 = 0.05;
+
+/// Most ambient samples drawn looking for one in the shell
+const MAX_ATTEMPTS: usize = 1_000_000;
+
+/// How far the offset from a sample to its projection can point off the normal, as a fraction of its length,
+/// before the projection is taken not to be the closest point
+const MAX_SIDEWAYS: f32 = 0.05;
+
+/// A small SplitMix64 generator, the same seed always gives the same numbers on every platform
+#[derive(Debug, Clone)]
+pub struct SeededRng {
+    state: u64
+}
+
+impl SeededRng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E3779B97F4A7C15);
+
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in [0, 1)
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    /// Uniform in the box between min and max
+    pub fn in_box(&mut self, min: Vector4<f32>, max: Vector4<f32>) -> Vector4<f32> {
+        min + Vector4::from_fn(|_, _| self.next_f32()).component_mul(&(max - min))
+    }
+}
+
+/// Scatters points uniformly by area over the part of a manifold inside a box. Points are drawn uniformly from the
+/// box and kept when they are within a thin shell of the manifold, then projected onto it. The shell is as thick on
+/// both sides, so the first order change in volume from the curvature cancels and the density is uniform up to the
+/// thickness squared times the curvature squared. It needs project_onto to be the closest point projection, others
+/// like the radial one of the Hypersphube squash the shell unevenly and are rejected.
+#[derive(Debug, Clone)]
+pub struct UniformSampler {
+    rng: SeededRng,
+    min: Vector4<f32>,
+    max: Vector4<f32>
+}
+
+impl UniformSampler {
+    pub fn new(seed: u64, min: Vector4<f32>, max: Vector4<f32>) -> Self {
+        assert!((0..4).all(|i| min[i] < max[i]), "min must be below max");
+
+        Self { rng: SeededRng::new(seed), min, max }
+    }
+
+    /// The next point, None if the manifold doesn't pass close enough to the box to find one or project_onto
+    /// moved a sample off the normal, which isn't the closest point projection
+    pub fn sample<M: Manifold + ?Sized>(&mut self, manifold: &M) -> Option<Vector4<f32>> {
+        for _ in 0..MAX_ATTEMPTS {
+            let pos = self.rng.in_box(self.min, self.max);
+            let on_curve = manifold.project_onto(pos);
+            let inside = (0..4).all(|i| self.min[i] <= on_curve[i] && on_curve[i] <= self.max[i]);
+
+            let offset = pos - on_curve;
+
+            if offset.magnitude() < SHELL_THICKNESS && inside {
+                let normal = manifold.normal_at(on_curve);
+                let sideways = (offset - normal * normal.dot(&offset)).magnitude();
+
+                return (sideways <= MAX_SIDEWAYS * offset.magnitude() + 1e-4).then_some(on_curve);
+            }
+        }
+
+        None
+    }
+
+    pub fn samples<M: Manifold + ?Sized>(&mut self, manifold: &M, count: usize) -> Option<Vec<Vector4<f32>>> {
+        (0..count).map(|_| self.sample(manifold)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::manifold::shapes3D::{ExtrudedShape, ExtrusionAxis, Sphere};
+    use crate::manifold::shapes4D::{Ditorus, Hypersphere, Hypersphube};
+    use super::*;
+
+    #[test]
+    fn test_samples_are_uniform() {
+        let sphere = Hypersphere::new(2.0);
+        let (min, max) = (Vector4::new(-2.5, -2.5, -2.5, -0.5), Vector4::new(2.5, 2.5, 2.5, 4.5));
+        let samples = UniformSampler::new(7, min, max).samples(&sphere, 4000).unwrap();
+
+        // Uniform points on a sphere have their center as their mean and radius² / 4 as the variance of each coordinate
+        let center = 2.0 * Vector4::<f32>::w();
+        let mean = samples.iter().sum::<Vector4<f32>>() / samples.len() as f32;
+        let variance = samples.iter()
+            .map(|pos| (pos - center).component_mul(&(pos - center)))
+            .sum::<Vector4<f32>>() / samples.len() as f32;
+
+        assert!((mean - center).amax() < 0.1);
+        assert!((variance - Vector4::repeat(1.0)).amax() < 0.1);
+
+        // Half of them have a positive x
+        let positive = samples.iter().filter(|pos| pos.x > 0.0).count() as f32 / samples.len() as f32;
+
+        assert!((positive - 0.5).abs() < 0.05);
+
+        let ditorus = Ditorus::new(4.0, 2.0, 1.0);
+        let (min, max) = (Vector4::new(-7.5, -7.5, -7.5, -0.5), Vector4::new(7.5, 7.5, 7.5, 14.5));
+        let first = UniformSampler::new(3, min, max).samples(&ditorus, 20).unwrap();
+        let second = UniformSampler::new(3, min, max).samples(&ditorus, 20).unwrap();
+
+        assert_eq!(first, second);
+
+        for pos in first {
+            assert!(ditorus.is_on_curve(pos));
+        }
+    }
+
+    #[test]
+    fn test_samples_on_cylinders_are_uniform() {
+        // The sphere's z goes along x, so the cylinder is a sphere around (1.5, 0, 0) extruded along w
+        let cylinder = ExtrudedShape::new(Sphere::new(1.5), ExtrusionAxis::W, None);
+        let (min, max) = (Vector4::new(-0.5, -2.0, -2.0, -3.0), Vector4::new(3.5, 2.0, 2.0, 3.0));
+        let samples = UniformSampler::new(5, min, max).samples(&cylinder, 4000).unwrap();
+
+        // Uniform by area is uniform on the sphere, with radius² / 3 as the variance of each of its coordinates,
+        // and uniform along w, with 6² / 12 as its variance
+        let center = Vector4::new(1.5, 0.0, 0.0, 0.0);
+        let mean = samples.iter().sum::<Vector4<f32>>() / samples.len() as f32;
+        let variance = samples.iter()
+            .map(|pos| (pos - center).component_mul(&(pos - center)))
+            .sum::<Vector4<f32>>() / samples.len() as f32;
+
+        assert!((mean - center).amax() < 0.1);
+        assert!((variance - Vector4::new(0.75, 0.75, 0.75, 3.0)).amax() < 0.1);
+
+        // Radial projections aren't the closest point
+        let hypersphube = Hypersphube::new(2.0, 4.0);
+        let (min, max) = (Vector4::new(-2.5, -2.5, -2.5, -0.5), Vector4::new(2.5, 2.5, 2.5, 4.5));
+
+        assert!(UniformSampler::new(7, min, max).samples(&hypersphube, 100).is_none());
+    }
+}
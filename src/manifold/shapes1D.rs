@@ -0,0 +1,103 @@
+use nalgebra::Vector2;
+use crate::manifold::EPSILON;
+
+/// A subset of a line, used as a factor of a Shape2D
+pub trait Shape1D: std::fmt::Debug {
+    fn description(&self) -> String {
+        String::from("1D shape")
+    }
+
+    /// A wgsl expression for the projection of the f32 `pos` given the vec2 `data` from get_data
+    fn project_onto_wgsl(&self, pos: &str, data: &str) -> String;
+
+    fn project_onto(&self, pos: f32) -> f32;
+
+    fn is_on_curve(&self, _pos: f32) -> bool {
+        panic!("{} does not currently impl is_on_curve", self.description())
+    }
+
+    fn get_data(&self) -> Vector2<f32>;
+
+    /// The position and velocity after moving along the shape from pos with velocity vel for time t,
+    /// for shapes that have a closed form
+    fn geodesic(&self, _pos: f32, _vel: f32, _t: f32) -> Option<(f32, f32)> {
+        None
+    }
+
+    /// A wgsl fn `{name}(pos: f32, vel: f32, t: f32, data: vec2<f32>) -> vec2<f32>` for the geodesic, returning the position in x and velocity in y
+    fn geodesic_wgsl(&self, _name: &str) -> Option<String> {
+        None
+    }
+}
+
+/// The whole line
+#[derive(Debug)]
+pub struct Line;
+
+impl Shape1D for Line {
+    fn description(&self) -> String {
+        String::from("Line")
+    }
+
+    fn project_onto_wgsl(&self, pos: &str, _data: &str) -> String {
+        pos.to_string()
+    }
+
+    fn project_onto(&self, pos: f32) -> f32 {
+        pos
+    }
+
+    fn is_on_curve(&self, _pos: f32) -> bool {
+        true
+    }
+
+    fn get_data(&self) -> Vector2<f32> {
+        Vector2::zeros()
+    }
+
+    fn geodesic(&self, pos: f32, vel: f32, t: f32) -> Option<(f32, f32)> {
+        Some((pos + t * vel, vel))
+    }
+
+    fn geodesic_wgsl(&self, name: &str) -> Option<String> {
+        Some(format!(
+            "fn {}(pos: f32, vel: f32, t: f32, data: vec2<f32>) -> vec2<f32> {{ return vec2(pos + t * vel, vel); }}",
+            name
+        ))
+    }
+}
+
+/// Everything from min to max
+#[derive(Debug)]
+pub struct Segment {
+    min: f32,
+    max: f32
+}
+
+impl Segment {
+    pub fn new(min: f32, max: f32) -> Self {
+        Self { min, max }
+    }
+}
+
+impl Shape1D for Segment {
+    fn description(&self) -> String {
+        String::from("Segment")
+    }
+
+    fn project_onto_wgsl(&self, pos: &str, data: &str) -> String {
+        format!("clamp({}, ({}).x, ({}).y)", pos, data, data)
+    }
+
+    fn project_onto(&self, pos: f32) -> f32 {
+        pos.clamp(self.min, self.max)
+    }
+
+    fn is_on_curve(&self, pos: f32) -> bool {
+        self.min - EPSILON < pos && pos < self.max + EPSILON
+    }
+
+    fn get_data(&self) -> Vector2<f32> {
+        Vector2::new(self.min, self.max)
+    }
+}
@@ -0,0 +1,291 @@
+use nalgebra::{Vector2, Vector4};
+use crate::manifold::EPSILON;
+use crate::manifold::shapes1D::Shape1D;
+
+/// A subset of a plane, used as a factor of a Product
+pub trait Shape2D: std::fmt::Debug {
+    fn description(&self) -> String {
+        String::from("2D shape")
+    }
+
+    /// A wgsl expression for the projection of the vec2 `pos` given the vec4 `data` from get_data
+    fn project_onto_wgsl(&self, pos: &str, data: &str) -> String;
+
+    fn project_onto(&self, pos: Vector2<f32>) -> Vector2<f32>;
+
+    fn is_on_curve(&self, _pos: Vector2<f32>) -> bool {
+        panic!("{} does not currently impl is_on_curve", self.description())
+    }
+
+    fn get_data(&self) -> Vector4<f32>;
+
+    /// How many of the coords are used, 1 for curves
+    fn dimension(&self) -> usize {
+        2
+    }
+
+    /// Coordinates on the shape for Product::intrinsic_coords, by default x and y
+    fn coords(&self, pos: Vector2<f32>) -> Vector2<f32> {
+        pos
+    }
+
+    /// A wgsl expression for the coords of the vec2 `pos` given the vec4 `data`
+    fn coords_wgsl(&self, pos: &str, _data: &str) -> String {
+        pos.to_string()
+    }
+
+    /// The point on the shape with the given coords
+    fn point_at_coords(&self, coords: Vector2<f32>) -> Vector2<f32> {
+        self.project_onto(coords)
+    }
+
+    /// A wgsl expression for point_at_coords of the vec2 `coords` given the vec4 `data`
+    fn point_at_coords_wgsl(&self, coords: &str, data: &str) -> String {
+        self.project_onto_wgsl(coords, data)
+    }
+
+    /// The position and velocity after moving along the shape from pos with velocity vel for time t,
+    /// for shapes that have a closed form
+    fn geodesic(&self, _pos: Vector2<f32>, _vel: Vector2<f32>, _t: f32) -> Option<(Vector2<f32>, Vector2<f32>)> {
+        None
+    }
+
+    /// A wgsl fn `{name}(pos: vec2<f32>, vel: vec2<f32>, t: f32, data: vec4<f32>) -> vec4<f32>` for the geodesic,
+    /// returning the position in xy and velocity in zw
+    fn geodesic_wgsl(&self, _name: &str) -> Option<String> {
+        None
+    }
+}
+
+/// The whole plane
+#[derive(Debug)]
+pub struct Plane;
+
+impl Shape2D for Plane {
+    fn description(&self) -> String {
+        String::from("Plane")
+    }
+
+    fn project_onto_wgsl(&self, pos: &str, _data: &str) -> String {
+        pos.to_string()
+    }
+
+    fn project_onto(&self, pos: Vector2<f32>) -> Vector2<f32> {
+        pos
+    }
+
+    fn is_on_curve(&self, _pos: Vector2<f32>) -> bool {
+        true
+    }
+
+    fn get_data(&self) -> Vector4<f32> {
+        Vector4::zeros()
+    }
+
+    fn geodesic(&self, pos: Vector2<f32>, vel: Vector2<f32>, t: f32) -> Option<(Vector2<f32>, Vector2<f32>)> {
+        Some((pos + t * vel, vel))
+    }
+
+    fn geodesic_wgsl(&self, name: &str) -> Option<String> {
+        Some(format!(
+            "fn {}(pos: vec2<f32>, vel: vec2<f32>, t: f32, data: vec4<f32>) -> vec4<f32> {{ return vec4(pos + t * vel, vel); }}",
+            name
+        ))
+    }
+}
+
+#[derive(Debug)]
+pub struct Circle {
+    radius: f32,
+    center: Vector2<f32>
+}
+
+impl Circle {
+    pub fn new(radius: f32) -> Self {
+        Self {
+            radius, center: radius * Vector2::y()
+        }
+    }
+}
+
+impl Shape2D for Circle {
+    fn description(&self) -> String {
+        String::from("Circle")
+    }
+
+    fn project_onto_wgsl(&self, pos: &str, data: &str) -> String {
+        format!("(({data}).xy + ({data}).z * normalize({pos} - ({data}).xy))", pos = pos, data = data)
+    }
+
+    fn project_onto(&self, mut pos: Vector2<f32>) -> Vector2<f32> {
+        pos -= self.center;
+
+        if pos.magnitude() == 0.0 {
+            pos.y += 0.0001;
+        }
+
+        self.radius * pos.normalize() + self.center
+    }
+
+    fn is_on_curve(&self, pos: Vector2<f32>) -> bool {
+        ((pos - self.center).magnitude() - self.radius).abs() < EPSILON
+    }
+
+    fn get_data(&self) -> Vector4<f32> {
+        Vector4::new(self.center.x, self.center.y, self.radius, 0.0)
+    }
+
+    fn dimension(&self) -> usize {
+        1
+    }
+
+    /// The angle around the center
+    fn coords(&self, pos: Vector2<f32>) -> Vector2<f32> {
+        let q = pos - self.center;
+
+        Vector2::new(q.y.atan2(q.x), 0.0)
+    }
+
+    fn coords_wgsl(&self, pos: &str, data: &str) -> String {
+        format!("vec2(atan2(({pos}).y - ({data}).y, ({pos}).x - ({data}).x), 0.0)", pos = pos, data = data)
+    }
+
+    fn point_at_coords(&self, coords: Vector2<f32>) -> Vector2<f32> {
+        self.center + self.radius * Vector2::new(coords.x.cos(), coords.x.sin())
+    }
+
+    fn point_at_coords_wgsl(&self, coords: &str, data: &str) -> String {
+        format!("(({data}).xy + ({data}).z * vec2(cos(({coords}).x), sin(({coords}).x)))", coords = coords, data = data)
+    }
+
+    /// Goes around the circle at the speed of the tangent part of vel
+    fn geodesic(&self, pos: Vector2<f32>, vel: Vector2<f32>, t: f32) -> Option<(Vector2<f32>, Vector2<f32>)> {
+        let q = pos - self.center;
+        let tangent = vel - q.dot(&vel) / self.radius.powi(2) * q;
+        let speed = tangent.magnitude();
+        let unit_tangent = tangent / speed.max(1e-6);
+        let angle = t * speed / self.radius;
+
+        Some((
+            self.center + q * angle.cos() + self.radius * unit_tangent * angle.sin(),
+            speed * (unit_tangent * angle.cos() - q / self.radius * angle.sin())
+        ))
+    }
+
+    fn geodesic_wgsl(&self, name: &str) -> Option<String> {
+        Some(format!("\
+            fn {}(pos: vec2<f32>, vel: vec2<f32>, t: f32, data: vec4<f32>) -> vec4<f32> {{\
+          \n    let q = pos - data.xy;\
+          \n    let r = data.z;\
+          \n    let tangent = vel - dot(q, vel) / (r * r) * q;\
+          \n    let speed = length(tangent);\
+          \n    let unit_tangent = tangent / max(speed, 1e-6);\
+          \n    let angle = t * speed / r;\
+          \n    \
+          \n    return vec4(\
+          \n        data.xy + q * cos(angle) + r * unit_tangent * sin(angle),\
+          \n        speed * (unit_tangent * cos(angle) - q / r * sin(angle))\
+          \n    );\
+          \n}}\
+            ", name))
+    }
+}
+
+/// A filled in circle around the origin
+#[derive(Debug)]
+pub struct Disk {
+    radius: f32
+}
+
+impl Disk {
+    pub fn new(radius: f32) -> Self {
+        Self { radius }
+    }
+}
+
+impl Shape2D for Disk {
+    fn description(&self) -> String {
+        String::from("Disk")
+    }
+
+    fn project_onto_wgsl(&self, pos: &str, data: &str) -> String {
+        format!(
+            "select({pos}, ({data}).z * normalize({pos}), length({pos}) > ({data}).z)",
+            pos = pos, data = data
+        )
+    }
+
+    fn project_onto(&self, pos: Vector2<f32>) -> Vector2<f32> {
+        if pos.magnitude() > self.radius {
+            self.radius * pos.normalize()
+        } else {
+            pos
+        }
+    }
+
+    fn is_on_curve(&self, pos: Vector2<f32>) -> bool {
+        pos.magnitude() < self.radius + EPSILON
+    }
+
+    fn get_data(&self) -> Vector4<f32> {
+        Vector4::new(0.0, 0.0, self.radius, 0.0)
+    }
+}
+
+/// Two Shape1Ds along the two axes of the plane
+#[derive(Debug)]
+pub struct Product2D<A: Shape1D, B: Shape1D>(pub A, pub B);
+
+impl<A: Shape1D, B: Shape1D> Shape2D for Product2D<A, B> {
+    fn description(&self) -> String {
+        format!("{} x {}", self.0.description(), self.1.description())
+    }
+
+    fn project_onto_wgsl(&self, pos: &str, data: &str) -> String {
+        format!(
+            "vec2({}, {})",
+            self.0.project_onto_wgsl(&format!("({}).x", pos), &format!("({}).xy", data)),
+            self.1.project_onto_wgsl(&format!("({}).y", pos), &format!("({}).zw", data))
+        )
+    }
+
+    fn project_onto(&self, pos: Vector2<f32>) -> Vector2<f32> {
+        Vector2::new(self.0.project_onto(pos.x), self.1.project_onto(pos.y))
+    }
+
+    fn is_on_curve(&self, pos: Vector2<f32>) -> bool {
+        self.0.is_on_curve(pos.x) && self.1.is_on_curve(pos.y)
+    }
+
+    fn get_data(&self) -> Vector4<f32> {
+        let (a, b) = (self.0.get_data(), self.1.get_data());
+
+        Vector4::new(a.x, a.y, b.x, b.y)
+    }
+
+    fn geodesic(&self, pos: Vector2<f32>, vel: Vector2<f32>, t: f32) -> Option<(Vector2<f32>, Vector2<f32>)> {
+        let (x, x_vel) = self.0.geodesic(pos.x, vel.x, t)?;
+        let (y, y_vel) = self.1.geodesic(pos.y, vel.y, t)?;
+
+        Some((Vector2::new(x, y), Vector2::new(x_vel, y_vel)))
+    }
+
+    fn geodesic_wgsl(&self, name: &str) -> Option<String> {
+        Some(format!("\
+            {}\
+          \n\
+          \n{}\
+          \n\
+          \nfn {}(pos: vec2<f32>, vel: vec2<f32>, t: f32, data: vec4<f32>) -> vec4<f32> {{\
+          \n    let x = {}_x(pos.x, vel.x, t, data.xy);\
+          \n    let y = {}_y(pos.y, vel.y, t, data.zw);\
+          \n    \
+          \n    return vec4(x.x, y.x, x.y, y.y);\
+          \n}}\
+            ",
+            self.0.geodesic_wgsl(&format!("{}_x", name))?,
+            self.1.geodesic_wgsl(&format!("{}_y", name))?,
+            name, name, name
+        ))
+    }
+}
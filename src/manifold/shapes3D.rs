@@ -1,6 +1,8 @@
-use nalgebra::{Vector3, Vector4};
+use nalgebra::{Vector2, Vector3, Vector4};
 use winit::event::VirtualKeyCode;
-use crate::manifold::EPSILON;
+use crate::manifold::{EPSILON, vec4_wgsl};
+use crate::manifold::closest_point::*;
+use crate::manifold::expression::{Expr, Param, nudged};
 use super::Manifold;
 
 pub trait Shape3D: std::fmt::Debug {
@@ -12,47 +14,271 @@ pub trait Shape3D: std::fmt::Debug {
 
     fn project_onto(&self, pos: Vector3<f32>) -> Vector3<f32>;
 
+    /// The projection as an expression where `pos` is a vec3, see `Manifold::projection_expr`. Only Sphere and Torus
+    /// have one
+    fn projection_expr(&self) -> Option<Expr> {
+        None
+    }
+
     fn is_on_curve(&self, pos: Vector3<f32>) -> bool {
         panic!("{} does not currently impl is_on_curve", self.description())
     }
 
+    /// Whether the point is in the solid the shape bounds, used for the caps on ExtrudedShape
+    fn is_inside(&self, _pos: Vector3<f32>) -> bool {
+        panic!("{} does not currently impl is_inside", self.description())
+    }
+
+    /// The body of a wgsl function taking `pos: vec3<f32>` and returning a bool, see `is_inside`
+    fn is_inside_wgsl(&self) -> String {
+        panic!("{} does not currently impl is_inside_wgsl", self.description())
+    }
+
+    /// Coordinates on the surface for ExtrudedShape::intrinsic_coords, by default x and y
+    fn surface_coords(&self, pos: Vector3<f32>) -> Vector2<f32> {
+        pos.xy()
+    }
+
+    /// The body of a wgsl function taking `pos: vec3<f32>` and returning a vec2, see `surface_coords`
+    fn surface_coords_wgsl(&self) -> String {
+        String::from("return pos.xy;")
+    }
+
+    /// The point on the surface with the given surface_coords
+    fn point_at_surface_coords(&self, coords: Vector2<f32>) -> Vector3<f32> {
+        self.project_onto(Vector3::new(coords.x, coords.y, 0.0))
+    }
+
+    /// The body of a wgsl function taking `coords: vec2<f32>` and returning a vec3, see `point_at_surface_coords`.
+    /// It can use `project_onto_3d_part`
+    fn point_at_surface_coords_wgsl(&self) -> String {
+        String::from("return project_onto_3d_part(vec3(coords, 0.0));")
+    }
+
     fn get_data(&self) -> (Vector3<f32>, Vector3<f32>, Vector3<f32>, f32, f32, f32);
 }
 
+#[derive(Debug, Clone, Copy)]
+pub enum ExtrusionAxis {
+    X,
+    Y,
+    Z,
+    W,
+    Direction(Vector4<f32>)
+}
+
+impl ExtrusionAxis {
+    pub fn vector(&self) -> Vector4<f32> {
+        match self {
+            Self::X => Vector4::x(),
+            Self::Y => Vector4::y(),
+            Self::Z => Vector4::z(),
+            Self::W => Vector4::w(),
+            Self::Direction(direction) => direction.normalize()
+        }
+    }
+
+    /// The axes that the shape's (x, y, z) go along, the shape's z goes along w whenever it can
+    fn shape_basis(&self) -> [Vector4<f32>; 3] {
+        let mut basis = vec![self.vector()];
+
+        for candidate in [Vector4::w(), Vector4::x(), Vector4::y(), Vector4::z()] {
+            let orthogonal = basis.iter()
+                .fold(candidate, |orthogonal, axis| orthogonal - axis * axis.dot(&orthogonal));
+
+            if orthogonal.magnitude() > EPSILON && basis.len() < 4 {
+                basis.push(orthogonal.normalize());
+            }
+        }
+
+        [basis[2], basis[3], basis[1]]
+    }
+}
+
+/// A Shape3D extruded along an axis, optionally only for `length` centered on the origin with the ends capped off
 #[derive(Debug)]
-pub struct ExtrudedShape<SHAPE: Shape3D>(pub SHAPE);
+pub struct ExtrudedShape<SHAPE: Shape3D> {
+    shape: SHAPE,
+    axis: Vector4<f32>,
+    shape_basis: [Vector4<f32>; 3],
+    length: Option<f32>
+}
+
+impl<SHAPE: Shape3D> ExtrudedShape<SHAPE> {
+    pub fn new(shape: SHAPE, axis: ExtrusionAxis, length: Option<f32>) -> Self {
+        if let ExtrusionAxis::Direction(direction) = axis {
+            assert!(direction.magnitude() > 0.0, "direction can't be zero");
+        }
+
+        if let Some(length) = length {
+            assert!(length > 0.0, "length must be positive");
+        }
+
+        Self {
+            shape,
+            axis: axis.vector(),
+            shape_basis: axis.shape_basis(),
+            length
+        }
+    }
+
+    fn split(&self, pos: Vector4<f32>) -> (Vector3<f32>, f32) {
+        (
+            Vector3::new(
+                self.shape_basis[0].dot(&pos),
+                self.shape_basis[1].dot(&pos),
+                self.shape_basis[2].dot(&pos)
+            ),
+            self.axis.dot(&pos)
+        )
+    }
+
+    fn join(&self, shape_pos: Vector3<f32>, t: f32) -> Vector4<f32> {
+        shape_pos.x * self.shape_basis[0] +
+            shape_pos.y * self.shape_basis[1] +
+            shape_pos.z * self.shape_basis[2] +
+            t * self.axis
+    }
+}
 
 impl<SHAPE: Shape3D> Manifold for ExtrudedShape<SHAPE> {
     fn description(&self) -> String {
-        format!("Extruded: {}", self.0.description())
+        format!("Extruded: {}", self.shape.description())
     }
-    
+
     fn project_onto_wgsl(&self) -> String {
+        let caps = match self.length {
+            Some(length) => format!("\
+            \n    let half_length = {:?};\
+            \n    \
+            \n    if (abs(t) > half_length || (is_inside_3d_part(shape_pos) && half_length - abs(t) < length(pos2 - shape_pos))) {{\
+            \n        t = select(-half_length, half_length, t >= 0.0);\
+            \n        pos2 = select(pos2, shape_pos, is_inside_3d_part(shape_pos));\
+            \n    }}\
+            \n    ", length / 2.0),
+            None => String::new()
+        };
+        let is_inside = match self.length {
+            Some(_) => format!("\
+            \n\
+            \nfn is_inside_3d_part(pos: vec3<f32>) -> bool {{\
+            \n    {}\
+            \n}}\
+            ", self.shape.is_inside_wgsl()),
+            None => String::new()
+        };
+
         format!("\
-            let pos2 = project_onto_3d_part(vec3(pos.x, pos.y, pos.w)); \
-      \n    return vec4(pos2.x, pos2.y, pos.z, pos2.z);\
+            let axis = {};\
+      \n    let e1 = {};\
+      \n    let e2 = {};\
+      \n    let e3 = {};\
+      \n    var t = dot(pos, axis);\
+      \n    let shape_pos = vec3(dot(pos, e1), dot(pos, e2), dot(pos, e3));\
+      \n    var pos2 = project_onto_3d_part(shape_pos);\
+      \n    {}\
+      \n    return pos2.x * e1 + pos2.y * e2 + pos2.z * e3 + t * axis;\
       \n}}\
+      {}\
       \n\
       \nfn project_onto_3d_part(pos: vec3<f32>) -> vec3<f32> {{\
       \n    {}\
-        ", self.0.project_onto_wgsl())
+        ",
+            vec4_wgsl(self.axis),
+            vec4_wgsl(self.shape_basis[0]),
+            vec4_wgsl(self.shape_basis[1]),
+            vec4_wgsl(self.shape_basis[2]),
+            caps,
+            is_inside,
+            self.shape.project_onto_wgsl()
+        )
     }
 
     fn project_onto(&self, pos: Vector4<f32>) -> Vector4<f32> {
-        let proj_pos = self.0.project_onto(Vector3::new(pos.x, pos.y, pos.w));
-        
-        Vector4::new(proj_pos.x, proj_pos.y, pos.z, proj_pos.z)
+        let (shape_pos, mut t) = self.split(pos);
+        let mut proj_pos = self.shape.project_onto(shape_pos);
+
+        if let Some(length) = self.length {
+            let half_length = length / 2.0;
+            let is_inside = self.shape.is_inside(shape_pos);
+
+            // Outside of the ends goes to the cap or its rim, inside goes to whichever of the cap or side is closer
+            if t.abs() > half_length || (is_inside && half_length - t.abs() < (proj_pos - shape_pos).magnitude()) {
+                t = if t >= 0.0 { half_length } else { -half_length };
+
+                if is_inside {
+                    proj_pos = shape_pos;
+                }
+            }
+        }
+
+        self.join(proj_pos, t)
+    }
+
+    /// The shape's surface_coords and the distance along the axis, the caps use the coords of their rim
+    fn intrinsic_coords(&self, pos: Vector4<f32>) -> Vector3<f32> {
+        let (shape_pos, t) = self.split(pos);
+        let coords = self.shape.surface_coords(shape_pos);
+
+        Vector3::new(coords.x, coords.y, t)
+    }
+
+    fn intrinsic_coords_wgsl(&self) -> String {
+        format!("\
+                let shape_pos = vec3(dot(pos, {}), dot(pos, {}), dot(pos, {}));\
+          \n    \
+          \n    return vec3(surface_coords_3d_part(shape_pos), dot(pos, {}));\
+          \n}}\
+          \n\
+          \nfn surface_coords_3d_part(pos: vec3<f32>) -> vec2<f32> {{\
+          \n    {}\
+            ",
+            vec4_wgsl(self.shape_basis[0]),
+            vec4_wgsl(self.shape_basis[1]),
+            vec4_wgsl(self.shape_basis[2]),
+            vec4_wgsl(self.axis),
+            self.shape.surface_coords_wgsl()
+        )
+    }
+
+    fn point_at_intrinsic_coords(&self, coords: Vector3<f32>) -> Vector4<f32> {
+        self.join(self.shape.point_at_surface_coords(coords.xy()), coords.z)
+    }
+
+    fn point_at_intrinsic_coords_wgsl(&self) -> String {
+        format!("\
+                let shape_pos = point_at_surface_coords_3d_part(coords.xy);\
+          \n    \
+          \n    return shape_pos.x * {} + shape_pos.y * {} + shape_pos.z * {} + coords.z * {};\
+          \n}}\
+          \n\
+          \nfn point_at_surface_coords_3d_part(coords: vec2<f32>) -> vec3<f32> {{\
+          \n    {}\
+            ",
+            vec4_wgsl(self.shape_basis[0]),
+            vec4_wgsl(self.shape_basis[1]),
+            vec4_wgsl(self.shape_basis[2]),
+            vec4_wgsl(self.axis),
+            self.shape.point_at_surface_coords_wgsl()
+        )
     }
 
     fn is_on_curve(&self, pos: Vector4<f32>) -> bool {
-        self.0.is_on_curve(Vector3::new(pos.x, pos.y, pos.w))
+        let (shape_pos, t) = self.split(pos);
+
+        match self.length {
+            Some(length) =>
+                (self.shape.is_on_curve(shape_pos) && t.abs() < length / 2.0 + EPSILON) ||
+                    ((t.abs() - length / 2.0).abs() < EPSILON && self.shape.is_inside(shape_pos)),
+            None => self.shape.is_on_curve(shape_pos)
+        }
     }
 
     fn get_bytes(&self) -> Vec<u8> {
         let (
             v1, v2, v3,
             r1, r2, r3
-        ) = self.0.get_data();
+        ) = self.shape.get_data();
 
         bytemuck::cast_slice(&[
             v1.fixed_resize::<4, 1>(0.0).as_slice(),
@@ -62,6 +288,183 @@ impl<SHAPE: Shape3D> Manifold for ExtrudedShape<SHAPE> {
         ].concat()[..]).to_owned()
     }
 }
+
+/// The plane that a SpunShape gets spun around, the shape's z axis goes around in the other plane along with w
+#[derive(Debug, Clone, Copy)]
+pub enum SpinPlane {
+    Xy,
+    Xz,
+    Yz
+}
+
+impl SpinPlane {
+    /// The indices of the axes of the plane and the axis that gets spun with w
+    fn axes(&self) -> (usize, usize, usize) {
+        match self {
+            Self::Xy => (0, 1, 2),
+            Self::Xz => (0, 2, 1),
+            Self::Yz => (1, 2, 0)
+        }
+    }
+}
+
+/// A Shape3D revolved around a plane, the shape's (x, y) go along the plane and its z goes out from it.
+/// `radius` is the distance from the plane to the shape's origin, which ends up at the origin.
+#[derive(Debug)]
+pub struct SpunShape<SHAPE: Shape3D> {
+    shape: SHAPE,
+    radius: f32,
+    spin_plane: SpinPlane,
+    center: Vector4<f32>
+}
+
+impl<SHAPE: Shape3D> SpunShape<SHAPE> {
+    pub fn new(shape: SHAPE, radius: f32, spin_plane: SpinPlane) -> Self {
+        Self {
+            shape,
+            radius,
+            spin_plane,
+            center: -radius * Vector4::w()
+        }
+    }
+}
+
+impl<SHAPE: Shape3D> Manifold for SpunShape<SHAPE> {
+    fn description(&self) -> String {
+        format!("Spun around {:?}: {}", self.spin_plane, self.shape.description())
+    }
+
+    fn project_onto_wgsl(&self) -> String {
+        let (u, v, a) = self.spin_plane.axes();
+        let mut components = [""; 4];
+
+        components[u] = "pos2.x";
+        components[v] = "pos2.y";
+        components[a] = "spun.x";
+        components[3] = "spun.y - radius";
+
+        format!("\
+            let radius = manifold_info.v1.w;\
+      \n    let rotated = vec2(pos.{}, pos.w + radius);\
+      \n    let pos2 = project_onto_3d_part(vec3(pos.{}, pos.{}, length(rotated) - radius));\
+      \n    let spun = (pos2.z + radius) * normalize(rotated);\
+      \n    \
+      \n    return vec4({});\
+      \n}}\
+      \n\
+      \nfn project_onto_3d_part(pos: vec3<f32>) -> vec3<f32> {{\
+      \n    {}\
+        ", ['x', 'y', 'z'][a], ['x', 'y', 'z'][u], ['x', 'y', 'z'][v], components.join(", "), self.shape.project_onto_wgsl())
+    }
+
+    fn project_onto(&self, pos: Vector4<f32>) -> Vector4<f32> {
+        let (u, v, a) = self.spin_plane.axes();
+        let pos2 = pos - self.center;
+        let mut rotated = Vector2::new(pos2[a], pos2.w);
+
+        if rotated.magnitude() == 0.0 {
+            rotated.y += 0.0001;
+        }
+
+        let proj_pos = self.shape.project_onto(Vector3::new(pos2[u], pos2[v], rotated.magnitude() - self.radius));
+        let spun = (proj_pos.z + self.radius) * rotated.normalize();
+        let mut new_pos = Vector4::zeros();
+
+        new_pos[u] = proj_pos.x;
+        new_pos[v] = proj_pos.y;
+        new_pos[a] = spun.x;
+        new_pos.w = spun.y;
+
+        new_pos + self.center
+    }
+
+    /// The shape's surface_coords and the angle around the plane
+    fn intrinsic_coords(&self, pos: Vector4<f32>) -> Vector3<f32> {
+        let (u, v, a) = self.spin_plane.axes();
+        let pos2 = pos - self.center;
+        let rotated = Vector2::new(pos2[a], pos2.w);
+        let coords = self.shape.surface_coords(Vector3::new(pos2[u], pos2[v], rotated.magnitude() - self.radius));
+
+        Vector3::new(coords.x, coords.y, rotated.y.atan2(rotated.x))
+    }
+
+    fn intrinsic_coords_wgsl(&self) -> String {
+        let (u, v, a) = self.spin_plane.axes();
+
+        format!("\
+            let radius = manifold_info.v1.w;\
+      \n    let rotated = vec2(pos.{}, pos.w + radius);\
+      \n    \
+      \n    return vec3(surface_coords_3d_part(vec3(pos.{}, pos.{}, length(rotated) - radius)), atan2(rotated.y, rotated.x));\
+      \n}}\
+      \n\
+      \nfn surface_coords_3d_part(pos: vec3<f32>) -> vec2<f32> {{\
+      \n    {}\
+        ", ['x', 'y', 'z'][a], ['x', 'y', 'z'][u], ['x', 'y', 'z'][v], self.shape.surface_coords_wgsl())
+    }
+
+    fn point_at_intrinsic_coords(&self, coords: Vector3<f32>) -> Vector4<f32> {
+        let (u, v, a) = self.spin_plane.axes();
+        let shape_pos = self.shape.point_at_surface_coords(coords.xy());
+        let spun = (shape_pos.z + self.radius) * Vector2::new(coords.z.cos(), coords.z.sin());
+        let mut new_pos = Vector4::zeros();
+
+        new_pos[u] = shape_pos.x;
+        new_pos[v] = shape_pos.y;
+        new_pos[a] = spun.x;
+        new_pos.w = spun.y;
+
+        new_pos + self.center
+    }
+
+    fn point_at_intrinsic_coords_wgsl(&self) -> String {
+        let (u, v, a) = self.spin_plane.axes();
+        let mut components = [""; 4];
+
+        components[u] = "shape_pos.x";
+        components[v] = "shape_pos.y";
+        components[a] = "spun.x";
+        components[3] = "spun.y - radius";
+
+        format!("\
+            let radius = manifold_info.v1.w;\
+      \n    let shape_pos = point_at_surface_coords_3d_part(coords.xy);\
+      \n    let spun = (shape_pos.z + radius) * vec2(cos(coords.z), sin(coords.z));\
+      \n    \
+      \n    return vec4({});\
+      \n}}\
+      \n\
+      \nfn point_at_surface_coords_3d_part(coords: vec2<f32>) -> vec3<f32> {{\
+      \n    {}\
+        ", components.join(", "), self.shape.point_at_surface_coords_wgsl())
+    }
+
+    fn is_on_curve(&self, pos: Vector4<f32>) -> bool {
+        let (u, v, a) = self.spin_plane.axes();
+        let pos2 = pos - self.center;
+
+        self.shape.is_on_curve(Vector3::new(
+            pos2[u],
+            pos2[v],
+            Vector2::new(pos2[a], pos2.w).magnitude() - self.radius
+        ))
+    }
+
+    fn get_bytes(&self) -> Vec<u8> {
+        let (
+            v1, v2, v3,
+            r1, r2, r3
+        ) = self.shape.get_data();
+
+        bytemuck::cast_slice(&[
+            &[v1.x, v1.y, v1.z, self.radius],
+            v2.fixed_resize::<4, 1>(0.0).as_slice(),
+            v3.fixed_resize::<4, 1>(0.0).as_slice(),
+            &[r1, r2, r3]
+        ].concat()[..]).to_owned()
+    }
+}
+
 #[derive(Debug)]
 pub struct Sphere {
     radius: f32,
@@ -94,23 +497,61 @@ impl Shape3D for Sphere {
     }
 
     fn project_onto_wgsl(&self) -> String {
-        String::from("\
-            let center = vec3(manifold_info.v1.x, manifold_info.v1.y, manifold_info.v1.z);\
-      \n    return manifold_info.r1 * normalize(pos - center) + center;\
-        ")
+        self.projection_expr().unwrap().projection_wgsl(3).unwrap()
+    }
+
+    fn projection_expr(&self) -> Option<Expr> {
+        let center = Expr::param(Param::V1);
+
+        Some(Expr::param(Param::R1) * (Expr::pos() - center.clone()).normalize() + center)
     }
 
     fn project_onto(&self, pos: Vector3<f32>) -> Vector3<f32> {
-        self.radius * (pos - self.center).normalize() + self.center
+        nudged(pos, |pos| self.radius * (pos - self.center).normalize() + self.center)
     }
 
     fn is_on_curve(&self, mut pos: Vector3<f32>) -> bool {
         pos -= self.center;
 
-        pos.x.powi(2) +
+        (pos.x.powi(2) +
             pos.y.powi(2) +
             pos.z.powi(2) -
-            self.radius.powi(2) < EPSILON
+            self.radius.powi(2)).abs() < EPSILON
+    }
+
+    fn is_inside(&self, pos: Vector3<f32>) -> bool {
+        (pos - self.center).magnitude() < self.radius
+    }
+
+    fn is_inside_wgsl(&self) -> String {
+        String::from("return length(pos - manifold_info.v1.xyz) < manifold_info.r1;")
+    }
+
+    /// The angle from the x axis and the angle around it, the poles are away from the bottom where the origin is
+    fn surface_coords(&self, pos: Vector3<f32>) -> Vector2<f32> {
+        let q = pos - self.center;
+
+        Vector2::new((q.x / q.magnitude().max(1e-6)).clamp(-1.0, 1.0).acos(), q.z.atan2(q.y))
+    }
+
+    fn surface_coords_wgsl(&self) -> String {
+        String::from("\
+            let q = pos - manifold_info.v1.xyz;\
+      \n    \
+      \n    return vec2(acos(clamp(q.x / max(length(q), 1e-6), -1.0, 1.0)), atan2(q.z, q.y));\
+        ")
+    }
+
+    fn point_at_surface_coords(&self, coords: Vector2<f32>) -> Vector3<f32> {
+        self.radius * Vector3::new(coords.x.cos(), coords.x.sin() * coords.y.cos(), coords.x.sin() * coords.y.sin()) + self.center
+    }
+
+    fn point_at_surface_coords_wgsl(&self) -> String {
+        String::from("\
+            let s = sin(coords.x);\
+      \n    \
+      \n    return manifold_info.r1 * vec3(cos(coords.x), s * cos(coords.y), s * sin(coords.y)) + manifold_info.v1.xyz;\
+        ")
     }
 }
 
@@ -148,38 +589,485 @@ impl Shape3D for Torus {
     }
 
     fn project_onto_wgsl(&self) -> String {
+        self.projection_expr().unwrap().projection_wgsl(3).unwrap()
+    }
+
+    fn projection_expr(&self) -> Option<Expr> {
+        let pos2 = Expr::var("pos2");
+        let major = Expr::var("major");
+
+        Some(Expr::let_in(
+            "pos2", Expr::pos() - Expr::param(Param::V1),
+            Expr::let_in(
+                "major", Expr::param(Param::R1) * Expr::vector(vec![pos2.clone().x(), Expr::constant(0.0), pos2.clone().z()]).normalize(),
+                major.clone() + Expr::param(Param::R2) * (pos2 - major).normalize() + Expr::param(Param::V1)
+            )
+        ))
+    }
+
+    fn project_onto(&self, pos: Vector3<f32>) -> Vector3<f32> {
+        nudged(pos, |pos| {
+            let pos = pos - self.center;
+            let mut new_pos = self.radius_major *
+                Vector3::new(pos.x, 0.0, pos.z)
+                    .normalize();
+
+            new_pos += self.radius_minor *
+                (pos - new_pos)
+                    .normalize();
+
+            new_pos + self.center
+        })
+    }
+
+    fn is_on_curve(&self, mut pos: Vector3<f32>) -> bool {
+        pos -= self.center;
+
+        (Vector2::new(pos.xz().magnitude() - self.radius_major, pos.y).magnitude() - self.radius_minor).abs() < EPSILON
+    }
+
+    fn is_inside(&self, mut pos: Vector3<f32>) -> bool {
+        pos -= self.center;
+
+        ((pos.x.powi(2) + pos.z.powi(2)).sqrt() - self.radius_major).powi(2) +
+            pos.y.powi(2) < self.radius_minor.powi(2)
+    }
+
+    fn is_inside_wgsl(&self) -> String {
+        String::from("\
+            let pos2 = pos - manifold_info.v1.xyz;\
+      \n    return length(vec2(length(pos2.xz) - manifold_info.r1, pos2.y)) < manifold_info.r2;\
+        ")
+    }
+
+    /// The angle around the big circle in the xz plane and the angle around the tube
+    fn surface_coords(&self, pos: Vector3<f32>) -> Vector2<f32> {
+        let q = pos - self.center;
+
+        Vector2::new(q.z.atan2(q.x), q.y.atan2(q.xz().magnitude() - self.radius_major))
+    }
+
+    fn surface_coords_wgsl(&self) -> String {
         String::from("\
-            let center = vec3(manifold_info.v1.x, manifold_info.v1.y, manifold_info.v1.z);\
-            let pos2 = pos - center;\
-      \n    var new_pos: vec3<f32> = vec3(0.0, 0.0, 0.0);\
+            let q = pos - manifold_info.v1.xyz;\
       \n    \
-      \n    new_pos += manifold_info.r1 * normalize(vec3(pos2.x, 0.0, pos.z));\
-      \n    new_pos += manifold_info.r2 * normalize(pos2 - new_pos);\
+      \n    return vec2(atan2(q.z, q.x), atan2(q.y, length(q.xz) - manifold_info.r1));\
+        ")
+    }
+
+    fn point_at_surface_coords(&self, coords: Vector2<f32>) -> Vector3<f32> {
+        let length_xz = self.radius_major + self.radius_minor * coords.y.cos();
+
+        Vector3::new(length_xz * coords.x.cos(), self.radius_minor * coords.y.sin(), length_xz * coords.x.sin()) + self.center
+    }
+
+    fn point_at_surface_coords_wgsl(&self) -> String {
+        String::from("\
+            let length_xz = manifold_info.r1 + manifold_info.r2 * cos(coords.y);\
       \n    \
-      \n    return new_pos + center;\
+      \n    return vec3(length_xz * cos(coords.x), manifold_info.r2 * sin(coords.y), length_xz * sin(coords.x)) + manifold_info.v1.xyz;\
+        ")
+    }
+}
+
+/// The ellipsoid (x / a)^2 + (y / b)^2 + (z / c)^2 = 1 with radii (a, b, c)
+#[derive(Debug)]
+pub struct Ellipsoid {
+    radii: Vector3<f32>,
+    center: Vector3<f32>
+}
+
+impl Ellipsoid {
+    pub fn new(radii: Vector3<f32>) -> Self {
+        Self {
+            radii, center: radii.z * Vector3::z()
+        }
+    }
+}
+
+impl Shape3D for Ellipsoid {
+    fn description(&self) -> String {
+        String::from("Ellipsoid")
+    }
+
+    fn get_data(&self) -> (Vector3<f32>, Vector3<f32>, Vector3<f32>, f32, f32, f32) {
+        (
+            self.center,
+            self.radii,
+            Vector3::zeros(),
+
+            0.0,
+            0.0,
+            0.0
+        )
+    }
+
+    fn project_onto_wgsl(&self) -> String {
+        closest_on_ellipsoid_wgsl(3, "manifold_info.v1.xyz", "manifold_info.v2.xyz")
+    }
+
+    fn project_onto(&self, pos: Vector3<f32>) -> Vector3<f32> {
+        closest_on_ellipsoid(pos - self.center, self.radii) + self.center
+    }
+
+    fn is_on_curve(&self, pos: Vector3<f32>) -> bool {
+        ((pos - self.center).component_div(&self.radii).magnitude() - 1.0).abs() * self.radii.min() < EPSILON
+    }
+
+    fn is_inside(&self, pos: Vector3<f32>) -> bool {
+        (pos - self.center).component_div(&self.radii).magnitude() < 1.0
+    }
+
+    fn is_inside_wgsl(&self) -> String {
+        String::from("return length((pos - manifold_info.v1.xyz) / manifold_info.v2.xyz) < 1.0;")
+    }
+}
+
+/// A cylinder around the y axis with flat caps, `length` is the distance between the caps
+#[derive(Debug)]
+pub struct Cylinder {
+    radius: f32,
+    half_length: f32,
+    center: Vector3<f32>
+}
+
+impl Cylinder {
+    pub fn new(radius: f32, length: f32) -> Self {
+        Self {
+            radius, half_length: length / 2.0,
+            center: radius * Vector3::z()
+        }
+    }
+}
+
+impl Shape3D for Cylinder {
+    fn description(&self) -> String {
+        String::from("Cylinder")
+    }
+
+    fn get_data(&self) -> (Vector3<f32>, Vector3<f32>, Vector3<f32>, f32, f32, f32) {
+        (
+            self.center,
+            Vector3::zeros(),
+            Vector3::zeros(),
+
+            self.radius,
+            self.half_length,
+            0.0
+        )
+    }
+
+    fn project_onto_wgsl(&self) -> String {
+        String::from("\
+            let pos2 = pos - manifold_info.v1.xyz;\
+      \n    let radius = manifold_info.r1;\
+      \n    let half_length = manifold_info.r2;\
+      \n    let radial = length(pos2.xz);\
+      \n    let side = vec3(radius * normalize(pos2.xz), pos2.y).xzy;\
+      \n    let cap = vec3(pos2.x, select(-half_length, half_length, pos2.y >= 0.0), pos2.z);\
+      \n    \
+      \n    if (radial < radius && abs(pos2.y) < half_length) {\
+      \n        return select(cap, side, radius - radial < half_length - abs(pos2.y)) + manifold_info.v1.xyz;\
+      \n    }\
+      \n    \
+      \n    let disk = select(pos2.xz, radius * normalize(pos2.xz), radial > radius);\
+      \n    \
+      \n    return vec3(disk.x, clamp(pos2.y, -half_length, half_length), disk.y) + manifold_info.v1.xyz;\
         ")
     }
 
     fn project_onto(&self, mut pos: Vector3<f32>) -> Vector3<f32> {
         pos -= self.center;
-        let mut new_pos = Vector3::zeros();
 
-        if pos.x == 0.0 && pos.z == 0.0 {
+        let mut radial = pos.xz();
+        let cap_y = if pos.y >= 0.0 { self.half_length } else { -self.half_length };
+
+        if radial.magnitude() == 0.0 {
+            radial.x += 0.0001;
+        }
+
+        // Inside goes to whichever of the side or cap is closer, outside gets clamped onto the solid cylinder
+        let new_pos = if radial.magnitude() < self.radius && pos.y.abs() < self.half_length {
+            if self.radius - radial.magnitude() < self.half_length - pos.y.abs() {
+                let side = self.radius * radial.normalize();
+
+                Vector3::new(side.x, pos.y, side.y)
+            } else {
+                Vector3::new(pos.x, cap_y, pos.z)
+            }
+        } else {
+            let disk = if radial.magnitude() > self.radius { self.radius * radial.normalize() } else { radial };
+
+            Vector3::new(disk.x, pos.y.clamp(-self.half_length, self.half_length), disk.y)
+        };
+
+        new_pos + self.center
+    }
+
+    fn is_on_curve(&self, mut pos: Vector3<f32>) -> bool {
+        pos -= self.center;
+        let radial = pos.xz().magnitude();
+
+        ((radial - self.radius).abs() < EPSILON && pos.y.abs() < self.half_length + EPSILON) ||
+            ((pos.y.abs() - self.half_length).abs() < EPSILON && radial < self.radius + EPSILON)
+    }
+
+    fn is_inside(&self, mut pos: Vector3<f32>) -> bool {
+        pos -= self.center;
+
+        pos.xz().magnitude() < self.radius && pos.y.abs() < self.half_length
+    }
+
+    fn is_inside_wgsl(&self) -> String {
+        String::from("\
+            let pos2 = pos - manifold_info.v1.xyz;\
+      \n    return length(pos2.xz) < manifold_info.r1 && abs(pos2.y) < manifold_info.r2;\
+        ")
+    }
+}
+
+/// All the points `radius` away from a segment along the y axis, `length` is the length of the segment
+#[derive(Debug)]
+pub struct Capsule {
+    radius: f32,
+    half_length: f32,
+    center: Vector3<f32>
+}
+
+impl Capsule {
+    pub fn new(radius: f32, length: f32) -> Self {
+        Self {
+            radius, half_length: length / 2.0,
+            center: radius * Vector3::z()
+        }
+    }
+
+    fn closest_on_segment(&self, pos: Vector3<f32>) -> Vector3<f32> {
+        pos.y.clamp(-self.half_length, self.half_length) * Vector3::y()
+    }
+}
+
+impl Shape3D for Capsule {
+    fn description(&self) -> String {
+        String::from("Capsule")
+    }
+
+    fn get_data(&self) -> (Vector3<f32>, Vector3<f32>, Vector3<f32>, f32, f32, f32) {
+        (
+            self.center,
+            Vector3::zeros(),
+            Vector3::zeros(),
+
+            self.radius,
+            self.half_length,
+            0.0
+        )
+    }
+
+    fn project_onto_wgsl(&self) -> String {
+        String::from("\
+            let pos2 = pos - manifold_info.v1.xyz;\
+      \n    let segment = vec3(0.0, clamp(pos2.y, -manifold_info.r2, manifold_info.r2), 0.0);\
+      \n    \
+      \n    return segment + manifold_info.r1 * normalize(pos2 - segment) + manifold_info.v1.xyz;\
+        ")
+    }
+
+    fn project_onto(&self, mut pos: Vector3<f32>) -> Vector3<f32> {
+        pos -= self.center;
+        let segment = self.closest_on_segment(pos);
+
+        if (pos - segment).magnitude() == 0.0 {
             pos.x += 0.0001;
         }
 
-        new_pos += self.radius_major *
-            Vector3::new(pos.x, 0.0, pos.z)
-                .normalize();
+        segment + self.radius * (pos - segment).normalize() + self.center
+    }
+
+    fn is_on_curve(&self, mut pos: Vector3<f32>) -> bool {
+        pos -= self.center;
+
+        ((pos - self.closest_on_segment(pos)).magnitude() - self.radius).abs() < EPSILON
+    }
+
+    fn is_inside(&self, mut pos: Vector3<f32>) -> bool {
+        pos -= self.center;
+
+        (pos - self.closest_on_segment(pos)).magnitude() < self.radius
+    }
+
+    fn is_inside_wgsl(&self) -> String {
+        String::from("\
+            let pos2 = pos - manifold_info.v1.xyz;\
+      \n    return length(pos2 - vec3(0.0, clamp(pos2.y, -manifold_info.r2, manifold_info.r2), 0.0)) < manifold_info.r1;\
+        ")
+    }
+}
+
+/// The superellipsoid |x / a|^p + |y / b|^p + |z / c|^p = 1, an exponent of 2 gives an ellipsoid and larger
+/// exponents get closer to a box.  The exponent should be at least 1 for the projection to converge.
+#[derive(Debug)]
+pub struct Superquadric {
+    radii: Vector3<f32>,
+    exponent: f32,
+    center: Vector3<f32>
+}
+
+impl Superquadric {
+    pub fn new(radii: Vector3<f32>, exponent: f32) -> Self {
+        Self {
+            radii, exponent,
+            center: radii.z * Vector3::z()
+        }
+    }
+
+    fn implicit_function(&self, pos: Vector3<f32>) -> f32 {
+        (pos - self.center).component_div(&self.radii).iter()
+            .map(|x| x.abs().powf(self.exponent))
+            .sum::<f32>()
+            .powf(1.0 / self.exponent)
+    }
+}
+
+impl Shape3D for Superquadric {
+    fn description(&self) -> String {
+        String::from("Superquadric")
+    }
+
+    fn get_data(&self) -> (Vector3<f32>, Vector3<f32>, Vector3<f32>, f32, f32, f32) {
+        (
+            self.center,
+            self.radii,
+            Vector3::zeros(),
+
+            self.exponent,
+            0.0,
+            0.0
+        )
+    }
+
+    fn project_onto_wgsl(&self) -> String {
+        closest_on_superellipsoid_wgsl(3, "manifold_info.v1.xyz", "manifold_info.v2.xyz", "manifold_info.r1")
+    }
+
+    fn project_onto(&self, pos: Vector3<f32>) -> Vector3<f32> {
+        closest_on_superellipsoid(pos - self.center, self.radii, self.exponent) + self.center
+    }
+
+    fn is_on_curve(&self, pos: Vector3<f32>) -> bool {
+        (self.implicit_function(pos) - 1.0).abs() * self.radii.min() < EPSILON
+    }
+
+    fn is_inside(&self, pos: Vector3<f32>) -> bool {
+        self.implicit_function(pos) < 1.0
+    }
+
+    fn is_inside_wgsl(&self) -> String {
+        String::from("\
+            let scaled = abs(pos - manifold_info.v1.xyz) / manifold_info.v2.xyz;\
+      \n    return dot(pow(scaled, vec3(manifold_info.r1)), vec3(1.0)) < 1.0;\
+        ")
+    }
+}
+
+/// All the points `rounding` away from a box with the given half size
+#[derive(Debug)]
+pub struct RoundedBox {
+    half_size: Vector3<f32>,
+    rounding: f32,
+    center: Vector3<f32>
+}
 
-        if (pos - new_pos).magnitude() == 0.0 {
-            pos.y += 0.0001;
+impl RoundedBox {
+    pub fn new(half_size: Vector3<f32>, rounding: f32) -> Self {
+        Self {
+            half_size, rounding,
+            center: (half_size.z + rounding) * Vector3::z()
         }
+    }
+
+    /// The signed distance to the box without the rounding
+    fn box_distance(&self, pos: Vector3<f32>) -> f32 {
+        let offset = (pos - self.center).abs() - self.half_size;
+
+        offset.sup(&Vector3::zeros()).magnitude() + offset.max().min(0.0)
+    }
+}
+
+impl Shape3D for RoundedBox {
+    fn description(&self) -> String {
+        String::from("Rounded box")
+    }
+
+    fn get_data(&self) -> (Vector3<f32>, Vector3<f32>, Vector3<f32>, f32, f32, f32) {
+        (
+            self.center,
+            self.half_size,
+            Vector3::zeros(),
+
+            self.rounding,
+            0.0,
+            0.0
+        )
+    }
+
+    fn project_onto_wgsl(&self) -> String {
+        String::from("\
+            let pos2 = pos - manifold_info.v1.xyz;\
+      \n    let half_size = manifold_info.v2.xyz;\
+      \n    let offset = abs(pos2) - half_size;\
+      \n    let signs = select(vec3(-1.0), vec3(1.0), pos2 >= vec3(0.0));\
+      \n    \
+      \n    if (all(offset < vec3(0.0))) {\
+      \n        let closest = max(offset.x, max(offset.y, offset.z));\
+      \n        let face = select(pos2, signs * (half_size + manifold_info.r1), offset == vec3(closest));\
+      \n        \
+      \n        return face + manifold_info.v1.xyz;\
+      \n    }\
+      \n    \
+      \n    let on_box = clamp(pos2, -half_size, half_size);\
+      \n    \
+      \n    return on_box + manifold_info.r1 * normalize(pos2 - on_box) + manifold_info.v1.xyz;\
+        ")
+    }
+
+    fn project_onto(&self, mut pos: Vector3<f32>) -> Vector3<f32> {
+        pos -= self.center;
+        let offset = pos.abs() - self.half_size;
+
+        // Inside the box the closest point is straight out through the nearest face
+        let new_pos = if offset.max() < 0.0 {
+            let mut face = pos;
+            let axis = offset.imax();
 
-        new_pos += self.radius_minor *
-            (pos - new_pos)
-                .normalize();
+            face[axis] = (self.half_size[axis] + self.rounding).copysign(pos[axis]);
+
+            face
+        } else {
+            let on_box = pos.zip_map(&self.half_size, |x, half_size| x.clamp(-half_size, half_size));
+
+            if (pos - on_box).magnitude() == 0.0 {
+                pos[offset.imax()] += 0.0001_f32.copysign(pos[offset.imax()]);
+            }
+
+            on_box + self.rounding * (pos - on_box).normalize()
+        };
 
         new_pos + self.center
     }
-}
\ No newline at end of file
+
+    fn is_on_curve(&self, pos: Vector3<f32>) -> bool {
+        (self.box_distance(pos) - self.rounding).abs() < EPSILON
+    }
+
+    fn is_inside(&self, pos: Vector3<f32>) -> bool {
+        self.box_distance(pos) < self.rounding
+    }
+
+    fn is_inside_wgsl(&self) -> String {
+        String::from("\
+            let offset = abs(pos - manifold_info.v1.xyz) - manifold_info.v2.xyz;\
+      \n    return length(max(offset, vec3(0.0))) + min(max(offset.x, max(offset.y, offset.z)), 0.0) < manifold_info.r1;\
+        ")
+    }
+}
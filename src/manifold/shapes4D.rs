@@ -1,6 +1,13 @@
-use nalgebra::Vector4;
+use nalgebra::{Vector2, Vector3, Vector4};
 use winit::event::VirtualKeyCode;
-use crate::manifold::{EPSILON, Manifold};
+use crate::manifold::{EPSILON, Manifold, Point, vec4_wgsl};
+use crate::manifold::intrinsic::{
+    great_circle, GREAT_CIRCLE_WGSL, hyperspherical_angles, point_at_hyperspherical_angles,
+    HYPERSPHERICAL_ANGLES_WGSL, POINT_AT_HYPERSPHERICAL_ANGLES_WGSL
+};
+use crate::manifold::implicit::ScalarField;
+use crate::manifold::expression::{Expr, Param, nudged};
+use crate::manifold::closest_point::*;
 
 #[derive(Debug)]
 pub struct Hyperplane;
@@ -20,7 +27,11 @@ impl Manifold for Hyperplane {
     }
 
     fn project_onto_wgsl(&self) -> String {
-        String::from("return vec4(pos.x, pos.y, pos.z, 0.0);")
+        self.projection_expr().unwrap().projection_wgsl(4).unwrap()
+    }
+
+    fn projection_expr(&self) -> Option<Expr> {
+        Some(Expr::vector(vec![Expr::pos().swizzle("xyz"), Expr::constant(0.0)]))
     }
 
     fn project_onto(&self, pos: Vector4<f32>) -> Vector4<f32> {
@@ -30,6 +41,43 @@ impl Manifold for Hyperplane {
     fn is_on_curve(&self, pos: Vector4<f32>) -> bool {
         pos.w.abs() < EPSILON
     }
+
+    fn normal_at(&self, _pos: Vector4<f32>) -> Vector4<f32> {
+        Vector4::w()
+    }
+
+    fn normal_at_wgsl(&self) -> String {
+        String::from("return vec4(0.0, 0.0, 0.0, 1.0);")
+    }
+
+    fn geodesic(&self, point: &Point, t: f32) -> Option<Point> {
+        let ray = point.ray.xyz().normalize().fixed_resize::<4, 1>(0.0);
+
+        Some(Point { pos: point.pos + t * ray, ray })
+    }
+
+    fn geodesic_wgsl(&self) -> Option<String> {
+        Some(String::from("\
+                let ray = vec4(normalize(point.ray.xyz), 0.0);\
+          \n    \
+          \n    return Point(point.pos + t * ray, ray);\
+            "))
+    }
+}
+
+/// The signed distance, positive on the +w side
+impl ScalarField for Hyperplane {
+    fn description(&self) -> String {
+        String::from("Hyperplane")
+    }
+
+    fn evaluate_wgsl(&self, name: &str) -> String {
+        format!("fn {}(pos: vec4<f32>) -> f32 {{ return pos.w; }}", name)
+    }
+
+    fn evaluate(&self, pos: Vector4<f32>) -> f32 {
+        pos.w
+    }
 }
 
 #[derive(Debug)]
@@ -61,24 +109,92 @@ impl Manifold for Hypersphere {
     }
 
     fn project_onto_wgsl(&self) -> String {
-        String::from("return manifold_info.r1 * normalize(pos - manifold_info.v1) + manifold_info.v1;")
+        self.projection_expr().unwrap().projection_wgsl(4).unwrap()
+    }
+
+    fn projection_expr(&self) -> Option<Expr> {
+        let center = Expr::param(Param::V1);
+
+        Some(Expr::param(Param::R1) * (Expr::pos() - center.clone()).normalize() + center)
     }
 
     fn project_onto(&self, pos: Vector4<f32>) -> Vector4<f32> {
-        self.radius * (pos - self.center).normalize() + self.center
+        nudged(pos, |pos| self.radius * (pos - self.center).normalize() + self.center)
+    }
+
+    fn normal_at(&self, pos: Vector4<f32>) -> Vector4<f32> {
+        (pos - self.center).normalize()
+    }
+
+    fn normal_at_wgsl(&self) -> String {
+        String::from("return normalize(pos - manifold_info.v1);")
+    }
+
+    fn geodesic(&self, point: &Point, t: f32) -> Option<Point> {
+        Some(great_circle(self.center, self.radius, point, t))
+    }
+
+    fn geodesic_wgsl(&self) -> Option<String> {
+        Some(String::from(GREAT_CIRCLE_WGSL))
+    }
+
+    fn intrinsic_coords(&self, pos: Vector4<f32>) -> Vector3<f32> {
+        hyperspherical_angles(self.center, pos)
+    }
+
+    fn intrinsic_coords_wgsl(&self) -> String {
+        String::from(HYPERSPHERICAL_ANGLES_WGSL)
+    }
+
+    fn point_at_intrinsic_coords(&self, coords: Vector3<f32>) -> Vector4<f32> {
+        point_at_hyperspherical_angles(self.center, self.radius, coords)
+    }
+
+    fn point_at_intrinsic_coords_wgsl(&self) -> String {
+        String::from(POINT_AT_HYPERSPHERICAL_ANGLES_WGSL)
     }
 
     fn is_on_curve(&self, mut pos: Vector4<f32>) -> bool {
         pos -= self.center;
 
-        pos.x.powi(2) +
+        (pos.x.powi(2) +
             pos.y.powi(2) +
             pos.z.powi(2) +
             pos.w.powi(2) -
-            self.radius.powi(2) < EPSILON
+            self.radius.powi(2)).abs() < EPSILON
     }
 }
 
+/// The signed distance, negative inside
+impl ScalarField for Hypersphere {
+    fn description(&self) -> String {
+        String::from("Hypersphere")
+    }
+
+    fn evaluate_wgsl(&self, name: &str) -> String {
+        format!(
+            "fn {}(pos: vec4<f32>) -> f32 {{ return length(pos - {}) - {:?}; }}",
+            name, vec4_wgsl(self.center), self.radius
+        )
+    }
+
+    fn evaluate(&self, pos: Vector4<f32>) -> f32 {
+        (pos - self.center).magnitude() - self.radius
+    }
+}
+
+/// (|x|^p + |y|^p + |z|^p + |w|^p)^(1 / p)
+fn p_norm(pos: Vector4<f32>, exponent: f32) -> f32 {
+    pos.iter().map(|x| x.abs().powf(exponent)).sum::<f32>().powf(1.0 / exponent)
+}
+
+/// The p-norm grows at most 4^(1 / p - 1 / 2) times as fast as the euclidean norm, this undoes that below p = 2
+fn norm_scale(exponent: f32) -> f32 {
+    4.0f32.powf((0.5 - 1.0 / exponent).min(0.0))
+}
+
+/// The 4D superellipsoid with equal radii, points are scaled onto it which isn't the closest point,
+/// see Superellipsoid for that
 #[derive(Debug)]
 pub struct Hypersphube {
     exponent: f32,
@@ -100,37 +216,45 @@ impl Manifold for Hypersphube {
     }
 
     fn project_onto_wgsl(&self) -> String {
-        String::from("\
-            let new_pos: vec4<f32> = pos - manifold_info.v1;\
-      \n    \
-      \n    return manifold_info.v1 + manifold_info.r1 * (new_pos / pow(\
-      \n        pow(abs(new_pos.x), manifold_info.r2) + \
-      \n        pow(abs(new_pos.y), manifold_info.r2) + \
-      \n        pow(abs(new_pos.z), manifold_info.r2) + \
-      \n        pow(abs(new_pos.w), manifold_info.r2)\
-      \n    , 1.0 / manifold_info.r2));\
-        ")
+        self.projection_expr().unwrap().projection_wgsl(4).unwrap()
     }
 
-    fn project_onto(&self, pos: Vector4<f32>) -> Vector4<f32> {
-        let new_pos = pos - self.center;
+    fn projection_expr(&self) -> Option<Expr> {
+        let new_pos = Expr::var("new_pos");
+        let exponent = Expr::param(Param::R2);
+        let norm = ["x", "y", "z", "w"].into_iter()
+            .map(|component| new_pos.clone().swizzle(component).abs().pow(exponent.clone()))
+            .reduce(|a, b| a + b)
+            .unwrap()
+            .pow(Expr::constant(1.0) / exponent);
+
+        Some(Expr::let_in(
+            "new_pos", Expr::pos() - Expr::param(Param::V1),
+            Expr::param(Param::V1) + Expr::param(Param::R1) * (new_pos.clone() / norm)
+        ))
+    }
 
-        self.center + self.radius * (new_pos / (
-            new_pos.x.abs().powf(self.exponent) +
-            new_pos.y.abs().powf(self.exponent) +
-            new_pos.z.abs().powf(self.exponent) +
-            new_pos.w.abs().powf(self.exponent)
-        ).powf(1.0 / (self.exponent as f32)))
+    fn project_onto(&self, pos: Vector4<f32>) -> Vector4<f32> {
+        nudged(pos, |pos| {
+            let new_pos = pos - self.center;
+
+            self.center + self.radius * (new_pos / (
+                new_pos.x.abs().powf(self.exponent) +
+                new_pos.y.abs().powf(self.exponent) +
+                new_pos.z.abs().powf(self.exponent) +
+                new_pos.w.abs().powf(self.exponent)
+            ).powf(1.0 / (self.exponent as f32)))
+        })
     }
 
     fn is_on_curve(&self, mut pos: Vector4<f32>) -> bool {
         pos -= self.center;
 
-        pos.x.abs().powf(self.exponent) +
+        (pos.x.abs().powf(self.exponent) +
             pos.y.abs().powf(self.exponent) +
             pos.z.abs().powf(self.exponent) +
             pos.w.abs().powf(self.exponent) -
-            self.radius.abs().powf(self.exponent) < EPSILON
+            self.radius.abs().powf(self.exponent)).abs() < EPSILON
     }
 
     fn get_bytes(&self) -> Vec<u8> {
@@ -143,6 +267,189 @@ impl Manifold for Hypersphube {
     }
 }
 
+/// Not exactly the signed distance, but it has the right sign and is never more than the distance for exponents of
+/// at least 1, the norm is scaled down by how much faster than the euclidean norm it can grow
+impl ScalarField for Hypersphube {
+    fn description(&self) -> String {
+        String::from("Hypersphube")
+    }
+
+    fn evaluate_wgsl(&self, name: &str) -> String {
+        format!(
+            "fn {}(pos: vec4<f32>) -> f32 {{ return (pow(dot(pow(abs(pos - {}), vec4({:?})), vec4(1.0)), {:?}) - {:?}) * {:?}; }}",
+            name, vec4_wgsl(self.center), self.exponent, 1.0 / self.exponent, self.radius, norm_scale(self.exponent)
+        )
+    }
+
+    fn evaluate(&self, pos: Vector4<f32>) -> f32 {
+        (p_norm(pos - self.center, self.exponent) - self.radius) * norm_scale(self.exponent)
+    }
+}
+
+/// The hyperellipsoid (x / a)^2 + (y / b)^2 + (z / c)^2 + (w / d)^2 = 1 with radii (a, b, c, d)
+#[derive(Debug)]
+pub struct Hyperellipsoid {
+    radii: Vector4<f32>,
+    center: Vector4<f32>
+}
+
+impl Hyperellipsoid {
+    pub fn new(radii: Vector4<f32>) -> Self {
+        Self {
+            radii, center: radii.w * Vector4::w()
+        }
+    }
+}
+
+impl Manifold for Hyperellipsoid {
+    fn description(&self) -> String {
+        String::from("Hyperellipsoid")
+    }
+
+    fn project_onto_wgsl(&self) -> String {
+        closest_on_ellipsoid_wgsl(4, "manifold_info.v1", "manifold_info.v2")
+    }
+
+    fn project_onto(&self, pos: Vector4<f32>) -> Vector4<f32> {
+        closest_on_ellipsoid(pos - self.center, self.radii) + self.center
+    }
+
+    fn is_on_curve(&self, pos: Vector4<f32>) -> bool {
+        ((pos - self.center).component_div(&self.radii).magnitude() - 1.0).abs() * self.radii.min() < EPSILON
+    }
+
+    fn get_bytes(&self) -> Vec<u8> {
+        bytemuck::cast_slice(&[
+            self.center.as_slice(),
+            self.radii.as_slice(),
+            Vector4::zeros().as_slice(),
+            &[ 0.0, 0.0, 0.0 ]
+        ].concat()[..]).to_owned()
+    }
+}
+
+/// Not exactly the signed distance, but it has the right sign and is never more than the distance
+impl ScalarField for Hyperellipsoid {
+    fn description(&self) -> String {
+        String::from("Hyperellipsoid")
+    }
+
+    fn evaluate_wgsl(&self, name: &str) -> String {
+        format!(
+            "fn {}(pos: vec4<f32>) -> f32 {{ return (length((pos - {}) / {}) - 1.0) * {:?}; }}",
+            name, vec4_wgsl(self.center), vec4_wgsl(self.radii), self.radii.min()
+        )
+    }
+
+    fn evaluate(&self, pos: Vector4<f32>) -> f32 {
+        ((pos - self.center).component_div(&self.radii).magnitude() - 1.0) * self.radii.min()
+    }
+}
+
+/// How points get moved onto a Superellipsoid
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SuperellipsoidProjection {
+    /// The nearest point, so advance_point walks along the actual surface
+    Closest,
+    /// Scaling towards the center like Hypersphube, cheaper but it stretches space near the corners
+    Radial
+}
+
+/// The superellipsoid |x / a|^p + |y / b|^p + |z / c|^p + |w / d|^p = 1 with radii (a, b, c, d) and exponent p,
+/// the exponent should be at least 1 for the closest point projection to converge
+#[derive(Debug)]
+pub struct Superellipsoid {
+    radii: Vector4<f32>,
+    exponent: f32,
+    projection: SuperellipsoidProjection,
+    center: Vector4<f32>
+}
+
+impl Superellipsoid {
+    pub fn new(radii: Vector4<f32>, exponent: f32, projection: SuperellipsoidProjection) -> Self {
+        Self {
+            radii, exponent, projection,
+            center: radii.w * Vector4::w()
+        }
+    }
+}
+
+impl Manifold for Superellipsoid {
+    fn description(&self) -> String {
+        format!("Superellipsoid ({:?})", self.projection)
+    }
+
+    fn project_onto_wgsl(&self) -> String {
+        match self.projection {
+            SuperellipsoidProjection::Closest =>
+                closest_on_superellipsoid_wgsl(4, "manifold_info.v1", "manifold_info.v2", "manifold_info.r1"),
+            SuperellipsoidProjection::Radial => String::from("\
+                    let pos2 = pos - manifold_info.v1;\
+              \n    let exponent = manifold_info.r1;\
+              \n    \
+              \n    return pos2 * pow(dot(pow(abs(pos2 / manifold_info.v2), vec4(exponent)), vec4(1.0)), -1.0 / exponent) + manifold_info.v1;\
+                ")
+        }
+    }
+
+    fn project_onto(&self, pos: Vector4<f32>) -> Vector4<f32> {
+        let mut new_pos = pos - self.center;
+
+        match self.projection {
+            SuperellipsoidProjection::Closest => closest_on_superellipsoid(new_pos, self.radii, self.exponent) + self.center,
+            SuperellipsoidProjection::Radial => {
+                if new_pos.magnitude() == 0.0 {
+                    new_pos.x += 0.0001;
+                }
+
+                radial_on_superellipsoid(new_pos, self.radii, self.exponent) + self.center
+            }
+        }
+    }
+
+    fn is_on_curve(&self, pos: Vector4<f32>) -> bool {
+        let norm = (pos - self.center).component_div(&self.radii).iter()
+            .map(|x| x.abs().powf(self.exponent))
+            .sum::<f32>()
+            .powf(1.0 / self.exponent);
+
+        (norm - 1.0).abs() * self.radii.min() < EPSILON
+    }
+
+    fn get_bytes(&self) -> Vec<u8> {
+        bytemuck::cast_slice(&[
+            self.center.as_slice(),
+            self.radii.as_slice(),
+            Vector4::zeros().as_slice(),
+            &[ self.exponent, 0.0, 0.0 ]
+        ].concat()[..]).to_owned()
+    }
+}
+
+/// Not exactly the signed distance, but it has the right sign and is never more than the distance for exponents of
+/// at least 1, like Hypersphube's field stretched by the radii
+impl ScalarField for Superellipsoid {
+    fn description(&self) -> String {
+        format!("Superellipsoid ({:?})", self.projection)
+    }
+
+    fn evaluate_wgsl(&self, name: &str) -> String {
+        format!(
+            "fn {}(pos: vec4<f32>) -> f32 {{ return (pow(dot(pow(abs((pos - {}) / {}), vec4({:?})), vec4(1.0)), {:?}) - 1.0) * {:?}; }}",
+            name,
+            vec4_wgsl(self.center),
+            vec4_wgsl(self.radii),
+            self.exponent,
+            1.0 / self.exponent,
+            self.radii.min() * norm_scale(self.exponent)
+        )
+    }
+
+    fn evaluate(&self, pos: Vector4<f32>) -> f32 {
+        (p_norm((pos - self.center).component_div(&self.radii), self.exponent) - 1.0) * self.radii.min() * norm_scale(self.exponent)
+    }
+}
+
 #[allow(non_snake_case)]
 #[derive(Debug)]
 pub struct Ditorus {
@@ -170,47 +477,93 @@ impl Manifold for Ditorus {
     }
 
     fn project_onto_wgsl(&self) -> String {
-        String::from("\
-            let pos2 = pos - manifold_info.v1;\
-      \n    var new_pos: vec4<f32> = vec4(0.0, 0.0, 0.0, 0.0);\
-      \n    \
-      \n    new_pos += manifold_info.r1 * normalize(vec4(pos2.x, 0.0, 0.0, pos2.w));\
-      \n    new_pos += manifold_info.r2 * normalize(vec4(pos2.x, 0.0, pos2.z, pos2.w) - new_pos);\
-      \n    new_pos += manifold_info.r3 * normalize(pos2 - new_pos);\
-      \n    \
-      \n    return new_pos + manifold_info.v1;\
-        ")
+        self.projection_expr().unwrap().projection_wgsl(4).unwrap()
     }
 
-    fn project_onto(&self, mut pos: Vector4<f32>) -> Vector4<f32> {
-        pos -= self.center;
-        let mut new_pos = Vector4::zeros();
+    fn projection_expr(&self) -> Option<Expr> {
+        let pos2 = Expr::var("pos2");
+        let major = Expr::var("major");
+        let minor = Expr::var("minor");
+        let zero = Expr::constant(0.0);
+
+        Some(Expr::let_in(
+            "pos2", Expr::pos() - Expr::param(Param::V1),
+            Expr::let_in(
+                "major", Expr::param(Param::R1) * Expr::vector(vec![
+                    pos2.clone().x(), zero.clone(), zero.clone(), pos2.clone().w()
+                ]).normalize(),
+                Expr::let_in(
+                    "minor", major.clone() + Expr::param(Param::R2) * (Expr::vector(vec![
+                        pos2.clone().x(), zero, pos2.clone().z(), pos2.clone().w()
+                    ]) - major).normalize(),
+                    minor.clone() + Expr::param(Param::R3) * (pos2 - minor).normalize() + Expr::param(Param::V1)
+                )
+            )
+        ))
+    }
 
-        if pos.x == 0.0 && pos.w == 0.0 {
-            pos.x += 0.0001;
-        }
+    fn project_onto(&self, pos: Vector4<f32>) -> Vector4<f32> {
+        nudged(pos, |pos| {
+            let pos = pos - self.center;
+            let mut new_pos = self.radius_major_major *
+                Vector4::new(pos.x, 0.0, 0.0, pos.w)
+                    .normalize();
+
+            new_pos += self.radius_major_minor *
+                (Vector4::new(pos.x, 0.0, pos.z, pos.w) - new_pos)
+                    .normalize();
+
+            new_pos += self.radius_minor_minor *
+                (pos - new_pos)
+                    .normalize();
+
+            new_pos + self.center
+        })
+    }
 
-        new_pos += self.radius_major_major *
-            Vector4::new(pos.x, 0.0, 0.0, pos.w)
-                .normalize();
+    /// The angle around the xw plane, then around the big torus and then around the smallest circle
+    fn intrinsic_coords(&self, pos: Vector4<f32>) -> Vector3<f32> {
+        let q = pos - self.center;
+        let major = Vector2::new(q.x, q.w).magnitude() - self.radius_major_major;
+        let minor = Vector2::new(major, q.z).magnitude() - self.radius_major_minor;
 
-        if pos.x == new_pos.x && pos.z == 0.0 && pos.w == new_pos.w {
-            pos.x += 0.0001;
-        }
+        Vector3::new(q.w.atan2(q.x), q.z.atan2(major), q.y.atan2(minor))
+    }
 
-        new_pos += self.radius_major_minor *
-            (Vector4::new(pos.x, 0.0, pos.z, pos.w) - new_pos)
-                .normalize();
+    fn intrinsic_coords_wgsl(&self) -> String {
+        String::from("\
+            let q = pos - manifold_info.v1;\
+      \n    let major = length(q.xw) - manifold_info.r1;\
+      \n    let minor = length(vec2(major, q.z)) - manifold_info.r2;\
+      \n    \
+      \n    return vec3(atan2(q.w, q.x), atan2(q.z, major), atan2(q.y, minor));\
+        ")
+    }
 
-        if (pos - new_pos).magnitude() == 0.0 {
-            pos.w += 0.0001;
-        }
+    fn point_at_intrinsic_coords(&self, coords: Vector3<f32>) -> Vector4<f32> {
+        let tube = self.radius_major_minor + self.radius_minor_minor * coords.z.cos();
+        let length_xw = self.radius_major_major + tube * coords.y.cos();
 
-        new_pos += self.radius_minor_minor *
-            (pos - new_pos)
-                .normalize();
+        Vector4::new(
+            length_xw * coords.x.cos(),
+            self.radius_minor_minor * coords.z.sin(),
+            tube * coords.y.sin(),
+            length_xw * coords.x.sin()
+        ) + self.center
+    }
 
-        new_pos + self.center
+    fn point_at_intrinsic_coords_wgsl(&self) -> String {
+        String::from("\
+            let tube = manifold_info.r2 + manifold_info.r3 * cos(coords.z);\
+      \n    let length_xw = manifold_info.r1 + tube * cos(coords.y);\
+      \n    \
+      \n    return vec4(\
+      \n        length_xw * cos(coords.x),\
+      \n        manifold_info.r3 * sin(coords.z),\
+      \n        tube * sin(coords.y),\
+      \n        length_xw * sin(coords.x)\
+      \n    ) + manifold_info.v1;\
+        ")
     }
 
     fn is_on_curve(&self, mut pos: Vector4<f32>) -> bool {
@@ -247,7 +600,38 @@ impl Manifold for Ditorus {
         }
     }
 }
-/*
+
+/// The signed distance, negative inside
+impl ScalarField for Ditorus {
+    fn description(&self) -> String {
+        String::from("Ditorus")
+    }
+
+    fn evaluate_wgsl(&self, name: &str) -> String {
+        format!("\
+            fn {}(pos: vec4<f32>) -> f32 {{\
+      \n    let pos2 = pos - {};\
+      \n    let major = vec2(length(pos2.xw) - {:?}, pos2.z);\
+      \n    \
+      \n    return length(vec2(length(major) - {:?}, pos2.y)) - {:?};\
+      \n}}\
+        ",
+            name,
+            vec4_wgsl(self.center),
+            self.radius_major_major,
+            self.radius_major_minor,
+            self.radius_minor_minor
+        )
+    }
+
+    fn evaluate(&self, pos: Vector4<f32>) -> f32 {
+        let pos = pos - self.center;
+        let major = Vector2::new(Vector2::new(pos.x, pos.w).magnitude() - self.radius_major_major, pos.z);
+
+        Vector2::new(major.magnitude() - self.radius_major_minor, pos.y).magnitude() - self.radius_minor_minor
+    }
+}
+#[derive(Debug, Clone, Copy)]
 pub enum TigerPairing {
     XyZw,
     XzWy,
@@ -261,9 +645,64 @@ impl TigerPairing {
             _          => (radius_major2, radius_major1)
         }
     }
+
+    /// Splits a position into the two planes that get rotated around, in the order (first, second)
+    pub fn split(&self, pos: Vector4<f32>) -> (Vector2<f32>, Vector2<f32>) {
+        match self {
+            Self::XyZw => (Vector2::new(pos.x, pos.y), Vector2::new(pos.z, pos.w)),
+            Self::XzWy => (Vector2::new(pos.x, pos.z), Vector2::new(pos.w, pos.y)),
+            Self::XwZy => (Vector2::new(pos.x, pos.w), Vector2::new(pos.z, pos.y))
+        }
+    }
+
+    /// Inverse of split
+    pub fn join(&self, a: Vector2<f32>, b: Vector2<f32>) -> Vector4<f32> {
+        match self {
+            Self::XyZw => Vector4::new(a.x, a.y, b.x, b.y),
+            Self::XzWy => Vector4::new(a.x, b.y, a.y, b.x),
+            Self::XwZy => Vector4::new(a.x, b.y, b.x, a.y)
+        }
+    }
+
+    // The axis in the plane without w that the center gets pushed along
+    fn non_w_axis(&self) -> Vector4<f32> {
+        match self {
+            Self::XwZy => Vector4::z(),
+            _          => Vector4::x()
+        }
+    }
+
+    pub(crate) fn split_components(&self) -> (&'static str, &'static str) {
+        match self {
+            Self::XyZw => ("xy", "zw"),
+            Self::XzWy => ("xz", "wy"),
+            Self::XwZy => ("xw", "zy")
+        }
+    }
+
+    /// Inverse of split_components for the wgsl vec2s named a and b
+    pub(crate) fn join_wgsl(&self, a: &str, b: &str) -> String {
+        match self {
+            Self::XyZw => format!("vec4({a}.x, {a}.y, {b}.x, {b}.y)", a = a, b = b),
+            Self::XzWy => format!("vec4({a}.x, {b}.y, {a}.y, {b}.x)", a = a, b = b),
+            Self::XwZy => format!("vec4({a}.x, {b}.y, {b}.x, {a}.y)", a = a, b = b)
+        }
+    }
+
+    fn join_expr(&self, a: Expr, b: Expr) -> Expr {
+        let new_a = Expr::var("new_a");
+        let new_b = Expr::var("new_b");
+
+        Expr::let_in("new_a", a, Expr::let_in("new_b", b, match self {
+            Self::XyZw => Expr::vector(vec![new_a.clone().x(), new_a.y(), new_b.clone().x(), new_b.y()]),
+            Self::XzWy => Expr::vector(vec![new_a.clone().x(), new_b.clone().y(), new_a.y(), new_b.x()]),
+            Self::XwZy => Expr::vector(vec![new_a.clone().x(), new_b.clone().y(), new_b.x(), new_a.y()])
+        }))
+    }
 }
 
-#[allow(non_snake_case)]
+/// The set of points where (|a| - R1)^2 + (|b| - R2)^2 = r^2 for a pairing of the axes into the planes a and b.
+/// The closest point is found by projecting (|a|, |b|) onto the circle of radius r around (R1, R2).
 #[derive(Debug)]
 pub struct Tiger {
     radius_major1: f32,
@@ -274,7 +713,6 @@ pub struct Tiger {
 }
 
 impl Tiger {
-    #[allow(non_snake_case)]
     pub fn new(
         radius_major1: f32,
         radius_major2: f32,
@@ -287,83 +725,113 @@ impl Tiger {
             radius_major_nw
         ) = tiger_pairing.get_ordered_radii(radius_major1, radius_major2);
 
+        // Puts the origin on the side of the tube facing w so the normal there is w
         Self {
             radius_major1,
             radius_major2,
             radius_minor,
             tiger_pairing,
             center: (
-                radius_major_w + if start_on_inside { -1.0 } else { 1.0 } * (
-                    radius_minor.powi(2) - radius_major_nw.powi(2)
-                ).sqrt()
-            ) * Vector4::w()
+                radius_major_w + if start_on_inside { -1.0 } else { 1.0 } * radius_minor
+            ) * Vector4::w() + radius_major_nw * tiger_pairing.non_w_axis()
         }
     }
 }
 
 impl Manifold for Tiger {
     fn description(&self) -> String {
-        String::from("Tiger")
+        format!("Tiger ({:?})", self.tiger_pairing)
     }
 
     fn project_onto_wgsl(&self) -> String {
-        String::from("\
-            let pos2 = pos - manifold_info.v1;
-      \n    var new_pos: vec4<f32> = vec4(0.0, 0.0, 0.0, 0.0);\
-      \n    \
-      \n    new_pos += manifold_info.r1 * normalize(vec4(pos2.x, 0.0, 0.0, pos2.w));\
-      \n    new_pos += manifold_info.r2 * normalize(vec4(pos2.x, 0.0, pos2.z, pos2.w) - new_pos);\
-      \n    new_pos += manifold_info.r3 * normalize(pos2 - new_pos);\
-      \n    \
-      \n    return new_pos + manifold_info.v1;\
-        ")
+        self.projection_expr().unwrap().projection_wgsl(4).unwrap()
     }
 
-    fn project_onto(&self, mut pos: Vector4<f32>) -> Vector4<f32> {
-        pos -= self.center;
-        let mut new_pos = Vector4::zeros();
+    fn projection_expr(&self) -> Option<Expr> {
+        let (a_components, b_components) = self.tiger_pairing.split_components();
+        let pos2 = Expr::pos() - Expr::param(Param::V1);
+        let (a, b) = (Expr::var("a"), Expr::var("b"));
+        let radii = Expr::var("radii");
+        let rho = Expr::var("rho");
+
+        Some(Expr::let_in("a", pos2.clone().swizzle(a_components),
+            Expr::let_in("b", pos2.swizzle(b_components),
+                Expr::let_in("radii", Expr::vector(vec![Expr::param(Param::R1), Expr::param(Param::R2)]),
+                    Expr::let_in("rho", radii.clone() + Expr::param(Param::R3) * (
+                        Expr::vector(vec![a.clone().length(), b.clone().length()]) - radii
+                    ).normalize(),
+                        self.tiger_pairing.join_expr(
+                            rho.clone().x() * a.normalize(),
+                            rho.y() * b.normalize()
+                        ) + Expr::param(Param::V1)
+                    )
+                )
+            )
+        ))
+    }
 
-        if pos.x == 0.0 && pos.w == 0.0 {
-            pos.x += 0.0001;
-        }
+    fn project_onto(&self, pos: Vector4<f32>) -> Vector4<f32> {
+        nudged(pos, |pos| {
+            let (a, b) = self.tiger_pairing.split(pos - self.center);
+            let radii = Vector2::new(self.radius_major1, self.radius_major2);
+            let rho = radii + self.radius_minor * (Vector2::new(a.magnitude(), b.magnitude()) - radii).normalize();
+
+            self.tiger_pairing.join(
+                rho.x * a.normalize(),
+                rho.y * b.normalize()
+            ) + self.center
+        })
+    }
 
-        new_pos += self.radius_major_major *
-            Vector4::new(pos.x, 0.0, 0.0, pos.w)
-                .normalize();
+    /// The angles around the planes a and b, then the angle around the tube starting from its outside in plane a
+    fn intrinsic_coords(&self, pos: Vector4<f32>) -> Vector3<f32> {
+        let (a, b) = self.tiger_pairing.split(pos - self.center);
 
-        if pos.x == new_pos.x && pos.z == 0.0 && pos.w == new_pos.w {
-            pos.x += 0.0001;
-        }
+        Vector3::new(
+            a.y.atan2(a.x),
+            b.y.atan2(b.x),
+            (b.magnitude() - self.radius_major2).atan2(a.magnitude() - self.radius_major1)
+        )
+    }
 
-        new_pos += self.radius_major_minor *
-            (Vector4::new(pos.x, 0.0, pos.z, pos.w) - new_pos)
-                .normalize();
+    fn intrinsic_coords_wgsl(&self) -> String {
+        let (a, b) = self.tiger_pairing.split_components();
 
-        if (pos - new_pos).magnitude() == 0.0 {
-            pos.w += 0.0001;
-        }
+        format!("\
+                let q = pos - manifold_info.v1;\
+          \n    let a = q.{};\
+          \n    let b = q.{};\
+          \n    \
+          \n    return vec3(atan2(a.y, a.x), atan2(b.y, b.x), atan2(length(b) - manifold_info.r2, length(a) - manifold_info.r1));\
+            ", a, b)
+    }
 
-        new_pos += self.radius_minor_minor *
-            (pos - new_pos)
-                .normalize();
+    fn point_at_intrinsic_coords(&self, coords: Vector3<f32>) -> Vector4<f32> {
+        let rho = Vector2::new(self.radius_major1, self.radius_major2) +
+            self.radius_minor * Vector2::new(coords.z.cos(), coords.z.sin());
 
-        new_pos + self.center
+        self.tiger_pairing.join(
+            rho.x * Vector2::new(coords.x.cos(), coords.x.sin()),
+            rho.y * Vector2::new(coords.y.cos(), coords.y.sin())
+        ) + self.center
     }
 
-    fn is_on_curve(&self, mut pos: Vector4<f32>) -> bool {
-        pos -= self.center;
+    fn point_at_intrinsic_coords_wgsl(&self) -> String {
+        format!("\
+                let rho = vec2(manifold_info.r1, manifold_info.r2) + manifold_info.r3 * vec2(cos(coords.z), sin(coords.z));\
+          \n    let a = rho.x * vec2(cos(coords.x), sin(coords.x));\
+          \n    let b = rho.y * vec2(cos(coords.y), sin(coords.y));\
+          \n    \
+          \n    return {} + manifold_info.v1;\
+            ", self.tiger_pairing.join_wgsl("a", "b"))
+    }
 
-        match self.tiger_pairing {
-            TigerPairing::XyZw =>
-                ((pos.x.powi(2) + pos.y.powi(2)).sqrt() - self.radius_major1).powi(2) +
-                    ((pos.z.powi(2) + pos.w.powi(2)).sqrt() - self.radius_major2).powi(2),
-            TigerPairing::XzWy =>
-                ((pos.x.powi(2) + pos.z.powi(2)).sqrt() - self.radius_major1).powi(2) +
-                    ((pos.w.powi(2) + pos.y.powi(2)).sqrt() - self.radius_major2).powi(2),
-            TigerPairing::XwZy =>
-                ((pos.x.powi(2) + pos.w.powi(2)).sqrt() - self.radius_major1).powi(2) +
-                    ((pos.z.powi(2) + pos.y.powi(2)).sqrt() - self.radius_major2).powi(2),
-        } - self.radius_minor.powi(2) < EPSILON
+    fn is_on_curve(&self, pos: Vector4<f32>) -> bool {
+        let (a, b) = self.tiger_pairing.split(pos - self.center);
+
+        ((a.magnitude() - self.radius_major1).powi(2) +
+            (b.magnitude() - self.radius_major2).powi(2) -
+            self.radius_minor.powi(2)).abs() < EPSILON
     }
 
     fn get_bytes(&self) -> Vec<u8> {
@@ -371,7 +839,40 @@ impl Manifold for Tiger {
             self.center.as_slice(),
             Vector4::zeros().as_slice(),
             Vector4::zeros().as_slice(),
-            &[ self.radius_major1, self.radius_major2, self.radius_minor]
+            &[ self.radius_major1, self.radius_major2, self.radius_minor ]
         ].concat()[..]).to_owned()
     }
-}*/
\ No newline at end of file
+}
+
+/// The signed distance, negative inside
+impl ScalarField for Tiger {
+    fn description(&self) -> String {
+        format!("Tiger ({:?})", self.tiger_pairing)
+    }
+
+    fn evaluate_wgsl(&self, name: &str) -> String {
+        let (a, b) = self.tiger_pairing.split_components();
+
+        format!("\
+            fn {}(pos: vec4<f32>) -> f32 {{\
+      \n    let pos2 = pos - {};\
+      \n    \
+      \n    return length(vec2(length(pos2.{}) - {:?}, length(pos2.{}) - {:?})) - {:?};\
+      \n}}\
+        ",
+            name,
+            vec4_wgsl(self.center),
+            a,
+            self.radius_major1,
+            b,
+            self.radius_major2,
+            self.radius_minor
+        )
+    }
+
+    fn evaluate(&self, pos: Vector4<f32>) -> f32 {
+        let (a, b) = self.tiger_pairing.split(pos - self.center);
+
+        Vector2::new(a.magnitude() - self.radius_major1, b.magnitude() - self.radius_major2).magnitude() - self.radius_minor
+    }
+}
@@ -0,0 +1,121 @@
+use nalgebra::Vector4;
+use crate::manifold::Manifold;
+
+/// Most relaxation sweeps before giving up on converging
+const MAX_SWEEPS: usize = 10000;
+
+/// How far the straight line is pushed sideways where it passes through a point project_onto can't handle,
+/// like the center of a sphere between antipodal endpoints
+const PERTURBATION: f32 = 0.01;
+
+/// The shortest path between two points found by relaxing a polyline on the manifold. Each sweep moves every inner
+/// sample towards the midpoint of its neighbours and projects it back, which shortens the path until it is a geodesic with evenly
+/// spaced samples. It starts from the projected straight line, so it finds the geodesic nearest to that line.
+/// Identifications of quotient spaces are ignored. A path that hits a point where project_onto isn't finite stops there
+/// and isn't converged.
+#[derive(Debug, Clone)]
+pub struct ShortestPath {
+    pub points: Vec<Vector4<f32>>,
+    pub length: f32,
+    pub sweeps: usize,
+    /// How far the samples moved in the last sweep
+    pub residual: f32,
+    pub converged: bool
+}
+
+impl ShortestPath {
+    /// Relaxes a path of segments pieces until no sample moves more than tolerance in a sweep
+    pub fn new<M: Manifold + ?Sized>(manifold: &M, from: Vector4<f32>, to: Vector4<f32>, segments: usize, tolerance: f32) -> Self {
+        assert!(segments > 0, "the path needs at least one segment");
+        assert!(tolerance > 0.0, "tolerance must be positive");
+
+        let (from, to) = (manifold.project_onto(from), manifold.project_onto(to));
+        let mut points: Vec<Vector4<f32>> = (0..=segments)
+            .map(|i| {
+                let pos = from.lerp(&to, i as f32 / segments as f32);
+                let new_pos = manifold.project_onto(pos);
+
+                if new_pos.iter().all(|c| c.is_finite()) {
+                    new_pos
+                } else {
+                    manifold.project_onto(pos + Vector4::repeat(PERTURBATION))
+                }
+            })
+            .collect();
+        points[0] = from;
+        points[segments] = to;
+
+        // The optimal over-relaxation for a chain of springs, it takes about segments sweeps instead of segments²
+        let relaxation = 2.0 / (1.0 + (std::f32::consts::PI / segments as f32).sin());
+        let mut sweeps = 0;
+        let mut residual = 0.0f32;
+
+        while sweeps < MAX_SWEEPS {
+            sweeps += 1;
+            residual = 0.0;
+
+            for i in 1..segments {
+                let midpoint = 0.5 * (points[i - 1] + points[i + 1]);
+                let new_point = manifold.project_onto(points[i] + relaxation * (midpoint - points[i]));
+
+                let moved = (new_point - points[i]).magnitude();
+
+                // max would drop a NaN
+                if !moved.is_finite() {
+                    residual = moved;
+                    break;
+                }
+
+                residual = residual.max(moved);
+                points[i] = new_point;
+            }
+
+            if !residual.is_finite() || residual < tolerance {
+                break;
+            }
+        }
+
+        Self {
+            length: points.windows(2).map(|pair| (pair[1] - pair[0]).magnitude()).sum(),
+            converged: residual < tolerance,
+            points, sweeps, residual
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::manifold::shapes4D::{Hyperplane, Hypersphere};
+    use super::*;
+
+    #[test]
+    fn test_shortest_paths() {
+        let sphere = Hypersphere::new(2.0);
+        let path = ShortestPath::new(&sphere, Vector4::zeros(), Vector4::new(2.0, 0.0, 0.0, 2.0), 32, 1e-5);
+
+        // A quarter of a great circle, the chords fall short of it by under 1e-3
+        assert!(path.converged);
+        assert!((path.length - std::f32::consts::PI).abs() < 1e-3);
+
+        for pos in path.points.iter() {
+            assert!(sphere.is_on_curve(*pos));
+        }
+
+        // The middle sample is 45 degrees around the arc
+        let expected = Vector4::new(2.0f32.sqrt(), 0.0, 0.0, 2.0 - 2.0f32.sqrt());
+
+        assert!((path.points[16] - expected).magnitude() < 1e-3);
+
+        let path = ShortestPath::new(&Hyperplane, Vector4::new(1.0, 2.0, 3.0, 0.0), Vector4::new(-2.0, 6.0, 3.0, 0.0), 8, 1e-5);
+
+        assert!(path.converged && path.sweeps == 1);
+        assert!((path.length - 5.0).abs() < 1e-4);
+
+        // The straight line between antipodal points goes through the center, any half of a great circle is shortest
+        let path = ShortestPath::new(&sphere, Vector4::zeros(), Vector4::new(0.0, 0.0, 0.0, 4.0), 32, 1e-5);
+
+        assert!(path.points.iter().all(|pos| pos.iter().all(|c| c.is_finite())));
+        assert!(path.converged);
+        assert!((path.length - 2.0 * std::f32::consts::PI).abs() < 1e-2);
+    }
+}
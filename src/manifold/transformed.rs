@@ -0,0 +1,348 @@
+use nalgebra::{Matrix4, Vector3, Vector4};
+use winit::event::VirtualKeyCode;
+use crate::manifold::{Manifold, Point, MANIFOLD_DATA_LEN, MANIFOLD_INFO_SIZE};
+
+/// The first slot of ManifoldInfo.data used by Transformed, it takes the last 6 so the inner manifold can't use them
+const TRANSFORM_SLOT: usize = MANIFOLD_DATA_LEN - 6;
+
+/// Reads rotation, translation and scale out of the slots used by Transformed
+fn transform_wgsl() -> String {
+    format!("\
+            let rotation = mat4x4(\
+      \n        manifold_info.data[{}],\
+      \n        manifold_info.data[{}],\
+      \n        manifold_info.data[{}],\
+      \n        manifold_info.data[{}]\
+      \n    );\
+      \n    let translation = manifold_info.data[{}];\
+      \n    let scale = manifold_info.data[{}].x;\
+        ",
+        TRANSFORM_SLOT + 2,
+        TRANSFORM_SLOT + 3,
+        TRANSFORM_SLOT + 4,
+        TRANSFORM_SLOT + 5,
+        TRANSFORM_SLOT + 1,
+        TRANSFORM_SLOT
+    )
+}
+
+/// The rotation by angle from axis1 towards axis2
+pub fn plane_rotation(axis1: usize, axis2: usize, angle: f32) -> Matrix4<f32> {
+    let mut rotation = Matrix4::identity();
+
+    rotation[(axis1, axis1)] = angle.cos();
+    rotation[(axis1, axis2)] = -angle.sin();
+    rotation[(axis2, axis1)] = angle.sin();
+    rotation[(axis2, axis2)] = angle.cos();
+
+    rotation
+}
+
+/// A manifold moved by pos => scale * rotation * pos + translation, closest points are preserved by this so
+/// projecting is done by undoing the transform, projecting onto the inner manifold and redoing the transform.
+/// Steps and identifications of the inner manifold are done in its own space too. Transforms can't be nested,
+/// multiply the rotations together instead.
+#[derive(Debug)]
+pub struct Transformed<M: Manifold> {
+    manifold: M,
+    rotation: Matrix4<f32>,
+    translation: Vector4<f32>,
+    scale: f32
+}
+
+impl<M: Manifold> Transformed<M> {
+    /// The rotation has to be orthogonal with determinant 1 and the scale has to be positive
+    pub fn new(manifold: M, rotation: Matrix4<f32>, translation: Vector4<f32>, scale: f32) -> Self {
+        assert!((rotation.transpose() * rotation - Matrix4::identity()).abs().max() < 1e-4, "rotation must be orthogonal");
+        assert!(rotation.determinant() > 0.0, "rotation can't be a reflection");
+        assert!(scale > 0.0, "scale must be positive");
+
+        Self { manifold, rotation, translation, scale }
+    }
+
+    pub fn manifold(&self) -> &M {
+        &self.manifold
+    }
+
+    fn untransform(&self, pos: Vector4<f32>) -> Vector4<f32> {
+        self.rotation.transpose() * (pos - self.translation) / self.scale
+    }
+
+    fn transform(&self, pos: Vector4<f32>) -> Vector4<f32> {
+        self.scale * self.rotation * pos + self.translation
+    }
+
+    fn untransform_point(&self, point: &Point) -> Point {
+        Point {
+            pos: self.untransform(point.pos),
+            ray: self.rotation.transpose() * point.ray
+        }
+    }
+
+    fn transform_point(&self, point: &Point) -> Point {
+        Point {
+            pos: self.transform(point.pos),
+            ray: self.rotation * point.ray
+        }
+    }
+}
+
+impl<M: Manifold> Manifold for Transformed<M> {
+    fn description(&self) -> String {
+        format!("Transformed {}", self.manifold.description())
+    }
+
+    fn project_onto_wgsl(&self) -> String {
+        format!("\
+                {}\
+          \n    \
+          \n    let local_pos = transpose(rotation) * (pos - translation) / scale;\
+          \n    \
+          \n    return scale * rotation * project_onto_untransformed(local_pos) + translation;\
+          \n}}\
+          \n\
+          \nfn project_onto_untransformed(pos: vec4<f32>) -> vec4<f32> {{\
+          \n    {}\
+            ",
+            transform_wgsl(),
+            self.manifold.project_onto_wgsl()
+        )
+    }
+
+    fn step_along_curve(&self, point: Point, delta: f32) -> Point {
+        self.transform_point(&self.manifold.step_along_curve(self.untransform_point(&point), delta / self.scale))
+    }
+
+    /// The inner manifold's calls to project_onto_curve and geodesic are swapped for their untransformed versions
+    fn step_along_curve_wgsl(&self) -> String {
+        format!("\
+                {}\
+          \n    \
+          \n    let local_point = Point(\
+          \n        transpose(rotation) * (point.pos - translation) / scale,\
+          \n        transpose(rotation) * point.ray\
+          \n    );\
+          \n    let new_point = step_along_curve_untransformed(local_point, delta / scale);\
+          \n    \
+          \n    return Point(scale * rotation * new_point.pos + translation, rotation * new_point.ray);\
+          \n}}\
+          \n\
+          \nfn step_along_curve_untransformed(point: Point, delta: f32) -> Point {{\
+          \n    {}\
+            ",
+            transform_wgsl(),
+            self.manifold.step_along_curve_wgsl()
+                .replace("project_onto_curve(", "project_onto_untransformed(")
+                .replace("geodesic(", "geodesic_untransformed(")
+        )
+    }
+
+    fn geodesic(&self, point: &Point, t: f32) -> Option<Point> {
+        let new_point = self.manifold.geodesic(&self.untransform_point(point), t / self.scale)?;
+
+        Some(self.transform_point(&new_point))
+    }
+
+    fn geodesic_wgsl(&self) -> Option<String> {
+        Some(format!("\
+                {}\
+          \n    \
+          \n    let local_point = Point(\
+          \n        transpose(rotation) * (point.pos - translation) / scale,\
+          \n        transpose(rotation) * point.ray\
+          \n    );\
+          \n    let new_point = geodesic_untransformed(local_point, t / scale);\
+          \n    \
+          \n    return Point(scale * rotation * new_point.pos + translation, rotation * new_point.ray);\
+          \n}}\
+          \n\
+          \nfn geodesic_untransformed(point: Point, t: f32) -> Point {{\
+          \n    {}\
+            ",
+            transform_wgsl(),
+            self.manifold.geodesic_wgsl()?
+        ))
+    }
+
+    fn project_onto(&self, pos: Vector4<f32>) -> Vector4<f32> {
+        self.transform(self.manifold.project_onto(self.untransform(pos)))
+    }
+
+    /// The frame is rotated into the inner manifold's space and back along with the point
+    fn identify(&self, point: Point, frame: &mut [Vector4<f32>]) -> Point {
+        for vector in frame.iter_mut() {
+            *vector = self.rotation.transpose() * *vector;
+        }
+
+        let new_point = self.manifold.identify(self.untransform_point(&point), frame);
+
+        for vector in frame.iter_mut() {
+            *vector = self.rotation * *vector;
+        }
+
+        self.transform_point(&new_point)
+    }
+
+    fn identify_wgsl(&self) -> String {
+        format!("\
+                {}\
+          \n    \
+          \n    let local_point = Point(\
+          \n        transpose(rotation) * (point.pos - translation) / scale,\
+          \n        transpose(rotation) * point.ray\
+          \n    );\
+          \n    let new_point = identify_point_untransformed(local_point);\
+          \n    \
+          \n    return Point(scale * rotation * new_point.pos + translation, rotation * new_point.ray);\
+          \n}}\
+          \n\
+          \nfn identify_point_untransformed(point: Point) -> Point {{\
+          \n    {}\
+            ",
+            transform_wgsl(),
+            self.manifold.identify_wgsl()
+        )
+    }
+
+    fn is_on_curve(&self, pos: Vector4<f32>) -> bool {
+        self.manifold.is_on_curve(self.untransform(pos))
+    }
+
+    fn normal_at(&self, pos: Vector4<f32>) -> Vector4<f32> {
+        self.rotation * self.manifold.normal_at(self.untransform(pos))
+    }
+
+    fn in_bounds(&self, pos: Vector4<f32>) -> bool {
+        self.manifold.in_bounds(self.untransform(pos))
+    }
+
+    fn in_bounds_wgsl(&self) -> String {
+        format!("\
+                {}\
+          \n    \
+          \n    return in_bounds_untransformed(transpose(rotation) * (pos - translation) / scale);\
+          \n}}\
+          \n\
+          \nfn in_bounds_untransformed(pos: vec4<f32>) -> bool {{\
+          \n    {}\
+            ",
+            transform_wgsl(),
+            self.manifold.in_bounds_wgsl()
+        )
+    }
+
+    /// The inner manifold's coords, so they move along with it
+    fn intrinsic_coords(&self, pos: Vector4<f32>) -> Vector3<f32> {
+        self.manifold.intrinsic_coords(self.untransform(pos))
+    }
+
+    /// The inner manifold's calls to project_onto_curve are swapped for project_onto_untransformed, which is its projection in here
+    fn intrinsic_coords_wgsl(&self) -> String {
+        format!("\
+                {}\
+          \n    \
+          \n    return intrinsic_coords_untransformed(transpose(rotation) * (pos - translation) / scale);\
+          \n}}\
+          \n\
+          \nfn intrinsic_coords_untransformed(pos: vec4<f32>) -> vec3<f32> {{\
+          \n    {}\
+            ",
+            transform_wgsl(),
+            self.manifold.intrinsic_coords_wgsl().replace("project_onto_curve(", "project_onto_untransformed(")
+        )
+    }
+
+    fn point_at_intrinsic_coords(&self, coords: Vector3<f32>) -> Vector4<f32> {
+        self.transform(self.manifold.point_at_intrinsic_coords(coords))
+    }
+
+    fn point_at_intrinsic_coords_wgsl(&self) -> String {
+        format!("\
+                {}\
+          \n    \
+          \n    return scale * rotation * point_at_intrinsic_coords_untransformed(coords) + translation;\
+          \n}}\
+          \n\
+          \nfn point_at_intrinsic_coords_untransformed(coords: vec3<f32>) -> vec4<f32> {{\
+          \n    {}\
+            ",
+            transform_wgsl(),
+            self.manifold.point_at_intrinsic_coords_wgsl().replace("project_onto_curve(", "project_onto_untransformed(")
+        )
+    }
+
+    fn get_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.manifold.get_bytes();
+        let offset = MANIFOLD_INFO_SIZE - 16 * (MANIFOLD_DATA_LEN - TRANSFORM_SLOT);
+
+        assert!(bytes.len() <= offset, "{} uses the slots needed by Transformed", self.manifold.description());
+
+        bytes.resize(offset, 0);
+        bytes.extend_from_slice(bytemuck::cast_slice(&[
+            &[self.scale, 0.0, 0.0, 0.0],
+            self.translation.as_slice(),
+            self.rotation.as_slice()
+        ].concat()[..]));
+
+        bytes
+    }
+
+    fn change_on_keybinds(&mut self, key_code: &VirtualKeyCode) {
+        self.manifold.change_on_keybinds(key_code);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_PI_2;
+    use crate::manifold::shapes4D::{Hyperplane, Hypersphere};
+    use crate::manifold::quotient::Quotient;
+    use super::*;
+
+    #[test]
+    fn test_transformed_hypersphere() {
+        // Hypersphere::new(2.0) is centered at 2w, which gets rotated onto 2x then scaled and moved to (6, 1, 0, 0)
+        let manifold = Transformed::new(
+            Hypersphere::new(2.0),
+            plane_rotation(3, 0, FRAC_PI_2),
+            Vector4::new(0.0, 1.0, 0.0, 0.0),
+            3.0
+        );
+        let center = Vector4::new(6.0, 1.0, 0.0, 0.0);
+
+        for pos in [Vector4::new(1.0, 2.0, 3.0, 4.0), Vector4::new(-5.0, 0.0, 0.5, 0.0), Vector4::new(6.0, 1.0, 0.0, 9.0)] {
+            let expected = center + 6.0 * (pos - center).normalize();
+
+            assert!((manifold.project_onto(pos) - expected).magnitude() < 1e-4);
+        }
+
+        assert_eq!(manifold.get_bytes().len(), MANIFOLD_INFO_SIZE);
+    }
+
+    #[test]
+    fn test_transformed_identifications() {
+        let rotation = plane_rotation(0, 1, 0.5);
+        let manifold = Transformed::new(
+            Quotient::klein_bottle_circle(Hyperplane, Vector3::new(4.0, 6.0, 8.0)),
+            rotation,
+            Vector4::new(1.0, 0.0, 2.0, 0.0),
+            2.0
+        );
+        let mut frame = [rotation * Vector4::y()];
+
+        // Going around x once mirrors y, in the inner manifold's coordinates
+        let point = manifold.identify(Point {
+            pos: manifold.transform(Vector4::new(3.0, 1.0, 0.0, 0.0)),
+            ray: rotation * Vector4::x()
+        }, &mut frame);
+
+        assert!((point.pos - manifold.transform(Vector4::new(-1.0, -1.0, 0.0, 0.0))).magnitude() < 1e-4);
+        assert!((point.ray - rotation * Vector4::x()).magnitude() < 1e-4);
+        assert!((frame[0] + rotation * Vector4::y()).magnitude() < 1e-4);
+
+        // Steps are scaled along with the manifold
+        let point = manifold.step_along_curve(Point { pos: manifold.translation, ray: rotation * Vector4::x() }, 1.0);
+
+        assert!((point.pos - manifold.transform(Vector4::new(0.5, 0.0, 0.0, 0.0))).magnitude() < 1e-4);
+    }
+}
@@ -0,0 +1,170 @@
+use std::f32::consts::TAU;
+use nalgebra::Vector4;
+use crate::manifold::{EPSILON, Manifold, MANIFOLD_DATA_LEN};
+
+const PUSH_OUT_ITERATIONS: u32 = 16;
+
+/// The most control points that fit in ManifoldInfo.data while leaving room for Transformed
+pub const MAX_CURVE_POINTS: usize = MANIFOLD_DATA_LEN - 6;
+
+/// All the points `radius` away from a closed polyline through the control points.
+/// The closest point is found by checking every segment, then pushing out from it to the radius.  On the inside
+/// of a bend that can land too close to another segment, so that gets repeated until it stays `radius` away.
+#[derive(Debug)]
+pub struct TubeAroundCurve {
+    points: Vec<Vector4<f32>>,
+    radius: f32
+}
+
+impl TubeAroundCurve {
+    /// The curve gets moved so that the origin is on the tube next to the first point
+    pub fn new(mut points: Vec<Vector4<f32>>, radius: f32) -> Self {
+        assert!(points.len() >= 2, "a curve needs at least 2 points");
+        assert!(points.len() <= MAX_CURVE_POINTS, "a curve can have at most {} points", MAX_CURVE_POINTS);
+
+        let tangent = (points[1] - points[0]).normalize();
+        let mut out = Vector4::w() - tangent * tangent.w;
+
+        if out.magnitude() < EPSILON {
+            out = Vector4::x() - tangent * tangent.x;
+        }
+
+        let start = points[0] + radius * out.normalize();
+
+        for point in points.iter_mut() {
+            *point -= start;
+        }
+
+        Self { points, radius }
+    }
+
+    /// Samples a closed curve at evenly spaced t in [0, 1)
+    pub fn from_fn(curve: impl Fn(f32) -> Vector4<f32>, samples: usize, radius: f32) -> Self {
+        Self::new((0..samples).map(|i| curve(i as f32 / samples as f32)).collect(), radius)
+    }
+
+    /// The (p, q) torus knot winding p times around the z axis and q times through the hole of a torus in xyz
+    pub fn torus_knot(p: u32, q: u32, radius_major: f32, radius_minor: f32, samples: usize, radius: f32) -> Self {
+        Self::from_fn(|t| {
+            let (angle_p, angle_q) = (TAU * p as f32 * t, TAU * q as f32 * t);
+            let distance = radius_major + radius_minor * angle_q.cos();
+
+            Vector4::new(distance * angle_p.cos(), distance * angle_p.sin(), radius_minor * angle_q.sin(), 0.0)
+        }, samples, radius)
+    }
+
+    /// The curve with each coordinate amplitude_i * sin(frequency_i * t + phase_i), closed for whole number frequencies
+    pub fn lissajous(
+        amplitudes: Vector4<f32>,
+        frequencies: Vector4<f32>,
+        phases: Vector4<f32>,
+        samples: usize,
+        radius: f32
+    ) -> Self {
+        Self::from_fn(|t| {
+            (TAU * t * frequencies + phases).map(|angle| angle.sin()).component_mul(&amplitudes)
+        }, samples, radius)
+    }
+
+    pub fn points(&self) -> &[Vector4<f32>] {
+        &self.points
+    }
+
+    fn closest_on_curve(&self, pos: Vector4<f32>) -> Vector4<f32> {
+        (0..self.points.len())
+            .map(|i| {
+                let (a, b) = (self.points[i], self.points[(i + 1) % self.points.len()]);
+                let t = ((pos - a).dot(&(b - a)) / (b - a).magnitude_squared().max(1e-12)).clamp(0.0, 1.0);
+
+                a + t * (b - a)
+            })
+            .min_by(|a, b| (pos - a).magnitude().total_cmp(&(pos - b).magnitude()))
+            .unwrap()
+    }
+}
+
+impl Manifold for TubeAroundCurve {
+    fn description(&self) -> String {
+        String::from("Tube around curve")
+    }
+
+    fn project_onto_wgsl(&self) -> String {
+        format!("\
+            let count = i32(manifold_info.r1);\
+      \n    let radius = manifold_info.r2;\
+      \n    var new_pos = pos;\
+      \n    \
+      \n    for (var iteration = 0; iteration < {}; iteration += 1) {{\
+      \n        var closest = manifold_info.data[0];\
+      \n        \
+      \n        for (var i = 0; i < count; i += 1) {{\
+      \n            let a = manifold_info.data[i];\
+      \n            let segment = manifold_info.data[(i + 1) % count] - a;\
+      \n            let t = clamp(dot(new_pos - a, segment) / max(dot(segment, segment), 1e-12), 0.0, 1.0);\
+      \n            let candidate = a + t * segment;\
+      \n            \
+      \n            if (distance(new_pos, candidate) < distance(new_pos, closest)) {{\
+      \n                closest = candidate;\
+      \n            }}\
+      \n        }}\
+      \n        \
+      \n        if (abs(distance(new_pos, closest) - radius) < {:?}) {{\
+      \n            break;\
+      \n        }}\
+      \n        \
+      \n        new_pos = closest + radius * normalize(new_pos - closest);\
+      \n    }}\
+      \n    \
+      \n    return new_pos;\
+        ", PUSH_OUT_ITERATIONS, 0.01 * EPSILON)
+    }
+
+    fn project_onto(&self, mut pos: Vector4<f32>) -> Vector4<f32> {
+        for _ in 0..PUSH_OUT_ITERATIONS {
+            let mut closest = self.closest_on_curve(pos);
+
+            if ((pos - closest).magnitude() - self.radius).abs() < 0.01 * EPSILON {
+                break;
+            }
+
+            if (pos - closest).magnitude() == 0.0 {
+                pos.w += 0.0001;
+                closest = self.closest_on_curve(pos);
+            }
+
+            pos = closest + self.radius * (pos - closest).normalize();
+        }
+
+        pos
+    }
+
+    fn is_on_curve(&self, pos: Vector4<f32>) -> bool {
+        ((pos - self.closest_on_curve(pos)).magnitude() - self.radius).abs() < EPSILON
+    }
+
+    fn get_bytes(&self) -> Vec<u8> {
+        bytemuck::cast_slice(&[
+            Vector4::zeros().as_slice(),
+            Vector4::zeros().as_slice(),
+            Vector4::zeros().as_slice(),
+            &[ self.points.len() as f32, self.radius, 0.0, 0.0 ],
+            &self.points.iter().flat_map(|point| point.iter().copied()).collect::<Vec<_>>()
+        ].concat()[..]).to_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tube_starts_on_origin() {
+        let tube = TubeAroundCurve::torus_knot(2, 3, 4.0, 1.5, 48, 0.5);
+        let bytes = tube.get_bytes();
+        let points: &[f32] = bytemuck::cast_slice(&bytes[64..]);
+
+        assert!(tube.is_on_curve(Vector4::zeros()));
+        assert_eq!(points.len(), 4 * 48);
+        assert_eq!(&points[..4], tube.points()[0].as_slice());
+    }
+}
@@ -21,6 +21,7 @@ struct ManifoldInfo {
     r1: f32,
     r2: f32,
     r3: f32,
+    data: array<vec4<f32>, 64>,
 }
 
 @group(0) @binding(1) var<uniform> manifold_info: ManifoldInfo;
@@ -54,6 +55,8 @@ fn fs_main(@builtin(position) pos: vec4<f32>) -> @location(0) vec4<f32> {
 
     var add_color: vec4<f32> = vec4(0.3, 0.0, 0.0, 0.0);
 
+    var hit_wall = false;
+
     loop {
         if (i >= info.max_iterations) {
             add_color = vec4(0.0, 0.0, 0.0, 0.0);
@@ -61,6 +64,12 @@ fn fs_main(@builtin(position) pos: vec4<f32>) -> @location(0) vec4<f32> {
             break;
         }
 
+        if (!in_bounds(point.pos)) {
+            hit_wall = true;
+
+            break;
+        }
+
 //        if (abs(point.pos.z) < 0.05 && (pow(point.pos.y, 2.0) + pow(point.pos.x, 2.0) > pow(3.0, 2.0))) {
 //            if (point.pos.z < 0.0) {
 //                add_color = vec4(0.0, 0.0, 0.3, 0.0);
@@ -203,16 +212,18 @@ fn fs_main(@builtin(position) pos: vec4<f32>) -> @location(0) vec4<f32> {
         return vec4(0.0, 0.0, 0.0, 1.0);
     } else if (i == 0.0) {
         return vec4(1.0, 1.0, 1.0, 1.0);
+    } else if (hit_wall) {
+        return (1.0 - i / info.max_iterations) * vec4(0.8, 0.7, 0.5, 1.0);
     }
 
     let w = 20.0;
+    let coords = intrinsic_coords(point.pos);
     let t: f32 = (
-        round(0.5*cos(w * point.pos.x)+0.5) +
-        round(0.5*cos(w * point.pos.y)+0.5) + 
-        round(0.5*cos(w * point.pos.z)+0.5) +
-        round(0.5*cos(w * point.pos.w)+0.5) + 
+        round(0.5*cos(w * coords.x)+0.5) +
+        round(0.5*cos(w * coords.y)+0.5) + 
+        round(0.5*cos(w * coords.z)+0.5) +
         0.0
-    ) / 4.0;
+    ) / 3.0;
 
     return (1.0 - i / info.max_iterations) * normalize(vec4<f32>(
         t, t, (i / info.max_iterations),
@@ -248,7 +259,12 @@ fn follow_curve_loop(point: Point, n: u32, direction: f32) -> Point {
 }
 
 fn follow_curve(point: Point) -> Point {
-    let new_pos = project_onto_curve(point.pos + info.delta * point.ray);
+    return identify_point(step_along_curve(point, info.delta));
+}
+
+// The real step fn will be injected here, intrinsic manifolds use it to follow their geodesics
+fn step_along_curve(point: Point, delta: f32) -> Point {
+    let new_pos = project_onto_curve(point.pos + delta * point.ray);
 
     return Point(
         new_pos,
@@ -256,6 +272,99 @@ fn follow_curve(point: Point) -> Point {
     );
 }
 
+// The closed form geodesic will be injected here for manifolds that have one
+fn geodesic(point: Point, t: f32) -> Point {
+    return step_along_curve(point, t);
+}
+
+// The real normal fn will be injected here, by default it differentiates project_onto_curve
+fn normal_at(pos: vec4<f32>) -> vec4<f32> {
+    let on_curve = project_onto_curve(pos);
+    let h = 0.01;
+    var columns = array<vec4<f32>, 4>(
+        project_onto_curve(on_curve + vec4(h, 0.0, 0.0, 0.0)) - project_onto_curve(on_curve - vec4(h, 0.0, 0.0, 0.0)),
+        project_onto_curve(on_curve + vec4(0.0, h, 0.0, 0.0)) - project_onto_curve(on_curve - vec4(0.0, h, 0.0, 0.0)),
+        project_onto_curve(on_curve + vec4(0.0, 0.0, h, 0.0)) - project_onto_curve(on_curve - vec4(0.0, 0.0, h, 0.0)),
+        project_onto_curve(on_curve + vec4(0.0, 0.0, 0.0, h)) - project_onto_curve(on_curve - vec4(0.0, 0.0, 0.0, h))
+    );
+    var shortest = 0;
+
+    for (var i = 1; i < 4; i++) {
+        if (dot(columns[i], columns[i]) < dot(columns[shortest], columns[shortest])) {
+            shortest = i;
+        }
+    }
+
+    columns[shortest] = columns[3];
+
+    let normal = normalize(cross4(columns[0], columns[1], columns[2]));
+
+    return select(normal, -normal, dot(normal, pos - on_curve) < 0.0);
+}
+
+// An orthonormal basis of the tangent space at the closest point to pos
+fn tangent_basis(pos: vec4<f32>) -> mat3x4<f32> {
+    let normal = normal_at(pos);
+    var most_normal = 0;
+
+    for (var i = 1; i < 4; i++) {
+        if (abs(normal[i]) > abs(normal[most_normal])) {
+            most_normal = i;
+        }
+    }
+
+    var basis: array<vec4<f32>, 3>;
+    var count = 0;
+
+    for (var i = 0; i < 4; i++) {
+        if (i == most_normal) {
+            continue;
+        }
+
+        var axis = -normal[i] * normal;
+        axis[i] += 1.0;
+
+        for (var j = 0; j < count; j++) {
+            axis -= dot(axis, basis[j]) * basis[j];
+        }
+
+        basis[count] = normalize(axis);
+        count++;
+    }
+
+    return mat3x4(basis[0], basis[1], basis[2]);
+}
+
+// The vector perpendicular to a, b and c
+fn cross4(a: vec4<f32>, b: vec4<f32>, c: vec4<f32>) -> vec4<f32> {
+    return vec4(
+        determinant(mat3x3(a.yzw, b.yzw, c.yzw)),
+        -determinant(mat3x3(a.xzw, b.xzw, c.xzw)),
+        determinant(mat3x3(a.xyw, b.xyw, c.xyw)),
+        -determinant(mat3x3(a.xyz, b.xyz, c.xyz))
+    );
+}
+
+// The real identification fn will be injected here, quotient spaces use it to wrap rays around
+fn identify_point(point: Point) -> Point {
+    return point;
+}
+
+// The real walls will be injected here, rays stop where this is false
+fn in_bounds(pos: vec4<f32>) -> bool {
+    return true;
+}
+
+// The real chart will be injected here, by default points are colored by their x, y and z
+fn intrinsic_coords(pos: vec4<f32>) -> vec3<f32> {
+    return pos.xyz;
+}
+
+// The real inverse of the chart will be injected here
+fn point_at_intrinsic_coords(coords: vec3<f32>) -> vec4<f32> {
+    return project_onto_curve(vec4(coords, 0.0));
+}
+
 // The real project fn will be injected here
 fn project_onto_curve(pos: vec4<f32>) -> vec4<f32> {
 }
\ No newline at end of file
@@ -129,7 +129,7 @@ impl State {
 
         let manifold_buffer = device.create_buffer(&wgpu::BufferDescriptor {
             label: Some("manifold buffer"),
-            size: 4 * 2 * manifold.get_bytes().len() as u64,
+            size: MANIFOLD_INFO_SIZE.max(manifold.get_bytes().len()) as u64,
             usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
             mapped_at_creation: false
         });
@@ -235,7 +235,7 @@ impl State {
         if let Some(manifold) = manifold_option {
             self.manifold_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
                 label: Some("manifold buffer"),
-                size: 4 * 2 * manifold.get_bytes().len() as u64,
+                size: MANIFOLD_INFO_SIZE.max(manifold.get_bytes().len()) as u64,
                 usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
                 mapped_at_creation: false
             });